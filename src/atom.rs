@@ -0,0 +1,164 @@
+use crate::seen::claim_unseen;
+use crate::{Feed, FeedItem, State};
+use anyhow::Result;
+use async_trait::async_trait;
+use atom_syndication::Entry;
+use serde::Deserialize;
+
+const ATOM_SEEN_ITEMS: &str = "atom_seen_items";
+
+pub struct AtomFeed {
+    pub config: AtomConfig,
+}
+
+impl AtomFeed {
+    pub fn new(url: impl Into<String>, count: usize, retention: usize) -> Self {
+        let config = AtomConfig {
+            url: url.into(),
+            count,
+            retention,
+        };
+        AtomFeed { config }
+    }
+}
+
+#[async_trait]
+impl Feed for AtomFeed {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
+        let res = reqwest::get(&self.config.url).await?;
+        let content = res.bytes().await?;
+        let feed = atom_syndication::Feed::read_from(&content[..])?;
+
+        let outputs = feed
+            .entries()
+            .iter()
+            .take(self.config.count)
+            .map(|entry| AtomOutput::new(entry.clone()))
+            .collect();
+
+        // Atom requires an id on every entry.
+        claim_unseen(
+            state,
+            ATOM_SEEN_ITEMS,
+            self.config.retention,
+            outputs,
+            None,
+            |output| output.entry.id().to_string(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AtomConfig {
+    pub url: String,
+    pub count: usize,
+    pub retention: usize,
+}
+
+pub struct AtomOutput {
+    entry: Entry,
+    updated: String,
+}
+
+impl AtomOutput {
+    fn new(entry: Entry) -> Self {
+        let updated = entry.updated().to_rfc3339();
+        AtomOutput { entry, updated }
+    }
+
+    // Prefer the `alternate` link, which is the human readable page of an entry.
+    fn link(&self) -> &str {
+        let links = self.entry.links();
+        links
+            .iter()
+            .find(|link| link.rel() == "alternate")
+            .or_else(|| links.first())
+            .map(|link| link.href())
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> std::ops::Index<&'a str> for AtomOutput {
+    type Output = str;
+    fn index(&self, field: &'a str) -> &Self::Output {
+        match field {
+            "title" => self.entry.title().as_str(),
+            "link" => self.link(),
+            "description" | "summary" => self
+                .entry
+                .summary()
+                .map(|summary| summary.as_str())
+                .or_else(|| self.entry.content().and_then(|content| content.value()))
+                .unwrap_or_default(),
+            "author" => self
+                .entry
+                .authors()
+                .first()
+                .map(|author| author.name())
+                .unwrap_or_default(),
+            "updated" => &self.updated,
+            "id" => self.entry.id(),
+            _ => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_atom {
+    use super::*;
+    use crate::test_server::{feed_and_commit, TestResponse, TestServer};
+    use async_std::task;
+
+    fn atom(entries: &[(&str, &str)]) -> String {
+        let entries: String = entries
+            .iter()
+            .map(|(id, title)| {
+                format!(
+                    r#"<entry>
+                        <id>urn:{id}</id>
+                        <title>{title}</title>
+                        <updated>2020-06-01T08:00:00Z</updated>
+                        <author><name>Alice</name></author>
+                        <link rel="enclosure" href="https://example.com/{id}.mp3"/>
+                        <link rel="alternate" href="https://example.com/{id}"/>
+                        <summary>About {title}</summary>
+                    </entry>"#,
+                    id = id,
+                    title = title
+                )
+            })
+            .collect();
+        format!(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">
+                <id>urn:feed</id>
+                <title>Releases</title>
+                <updated>2020-06-01T08:00:00Z</updated>
+                {}
+            </feed>"#,
+            entries
+        )
+    }
+
+    #[test]
+    fn test_feed() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, atom(&[("2", "Two"), ("1", "One")])),
+            TestResponse::new(200, atom(&[("3", "Three"), ("2", "Two"), ("1", "One")])),
+        ]);
+        let feed = AtomFeed::new(server.url.clone(), 10, 10);
+        let mut state = State::new();
+
+        let news = feed_and_commit(&feed, &mut state);
+        assert_eq!(news.len(), 2);
+        assert_eq!(&news[0]["title"], "Two");
+        assert_eq!(&news[0]["link"], "https://example.com/2");
+        assert_eq!(&news[0]["summary"], "About Two");
+        assert_eq!(&news[0]["author"], "Alice");
+        assert_eq!(&news[0]["updated"], "2020-06-01T08:00:00+00:00");
+        assert_eq!(state[ATOM_SEEN_ITEMS], r#"["urn:1","urn:2"]"#);
+
+        let news = task::block_on(feed.feed(&mut state)).unwrap();
+        assert_eq!(news.len(), 1);
+        assert_eq!(&news[0]["id"], "urn:3");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::template::Template;
+use crate::web::{check_api_code, ensure_success, MessageFormat};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+pub const BARK_SERVER: &str = "https://api.day.app";
+
+// Pushes to an iOS device through a Bark server.
+pub struct BarkSink {
+    config: BarkConfig,
+}
+
+pub struct BarkConfig {
+    pub server: String,
+    pub device_key: String,
+    pub title: Template,
+    pub format: MessageFormat,
+    pub group: Option<String>,
+    pub sound: Option<String>,
+    // Opened when the notification is tapped, rendered with the item.
+    pub url: Option<Template>,
+}
+
+impl BarkSink {
+    pub fn new(config: BarkConfig) -> Self {
+        BarkSink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for BarkSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let mut payload = json!({
+            "device_key": config.device_key,
+            "title": config.title.render(item),
+        });
+        match config.format {
+            MessageFormat::Text => payload["body"] = input.into(),
+            MessageFormat::Markdown => payload["markdown"] = input.into(),
+        }
+        if let Some(ref group) = config.group {
+            payload["group"] = group.as_str().into();
+        }
+        if let Some(ref sound) = config.sound {
+            payload["sound"] = sound.as_str().into();
+        }
+        if let Some(ref url) = config.url {
+            payload["url"] = url.render(item).into();
+        }
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&format!("{}/push", config.server))
+            .json(&payload)
+            .send()
+            .await?;
+
+        let body = ensure_success(res).await?;
+        check_api_code("Bark", &body, "code", "200", "message")
+    }
+}
+
+#[cfg(test)]
+mod test_bark {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use crate::web::ApiError;
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"code": 200, "message": "success"}"#),
+            TestResponse::new(
+                200,
+                r#"{"code": 400, "message": "failed to get device token"}"#,
+            ),
+        ]);
+        let sink = BarkSink::new(BarkConfig {
+            server: server.url.clone(),
+            device_key: "device".to_string(),
+            title: "{title}".parse().unwrap(),
+            format: MessageFormat::Markdown,
+            group: Some("feeds".to_string()),
+            sound: None,
+            url: Some("{link}".parse().unwrap()),
+        });
+        let item = TestItem::new(&[("title", "News"), ("link", "https://example.com/1")]);
+
+        task::block_on(sink.sink("**hi**".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/push");
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "device_key": "device",
+                "title": "News",
+                "markdown": "**hi**",
+                "group": "feeds",
+                "url": "https://example.com/1"
+            })
+        );
+
+        let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+        let err = err.downcast::<ApiError>().unwrap();
+        assert_eq!(err.code, "400");
+        assert_eq!(err.message, "failed to get device token");
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{Error, Result};
 use async_std::task;
-use futures_util::{future, AsyncReadExt};
+use futures_util::future;
 use ifttt_action::{config::Config, Action, ActionRun, States};
 use std::fs::File;
 use std::path::Path;
@@ -34,7 +34,8 @@ fn main() -> Result<()> {
     let combined_futures = future::join_all(actions.iter_mut().map(|action| action.execute()));
 
     let result = task::block_on(combined_futures);
-    println!("The results are: \n{:#?}", result);
+    // Reported on stderr, so stdout only carries what the `stdout` sink prints.
+    eprintln!("The results are: \n{:#?}", result);
 
     let states: States = actions
         .into_iter()
@@ -0,0 +1,187 @@
+use crate::Indexable;
+use anyhow::Result;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum ConditionError {
+    InvalidRule(String),
+}
+
+impl Display for ConditionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConditionError::InvalidRule(rule) => write!(f, "Invalid condition: {}", rule),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Contains,
+}
+
+// Longer operators first, so `>=` isn't read as `>`.
+const OPERATORS: [(&str, Operator); 6] = [
+    (">=", Operator::Ge),
+    ("<=", Operator::Le),
+    ("==", Operator::Eq),
+    ("!=", Operator::Ne),
+    (">", Operator::Gt),
+    ("<", Operator::Lt),
+];
+
+// A rule like `temp_max > 35`, `wind_scale_day>=6` or `text_day contains rain`
+// checked against an item. `contains` needs spaces around it, the other
+// operators don't.
+//
+// Values are compared as numbers when both sides are numbers, and as strings
+// otherwise. A range such as HeWeather's wind scale `10-11` compares as its
+// upper bound. `contains` ignores case.
+#[derive(Debug)]
+pub struct Condition {
+    rule: String,
+    field: String,
+    operator: Operator,
+    value: String,
+}
+
+impl FromStr for Condition {
+    type Err = ConditionError;
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConditionError::InvalidRule(rule.to_string());
+        let rule = rule.trim();
+        let mut parts = rule.splitn(3, char::is_whitespace);
+
+        let (field, operator, value) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(field), Some("contains"), Some(value)) => (field, Operator::Contains, value),
+            _ => {
+                let (idx, symbol, operator) = OPERATORS
+                    .iter()
+                    .filter_map(|&(symbol, operator)| {
+                        rule.find(symbol).map(|idx| (idx, symbol, operator))
+                    })
+                    .min_by_key(|&(idx, _, _)| idx)
+                    .ok_or_else(invalid)?;
+                (&rule[..idx], operator, &rule[idx + symbol.len()..])
+            }
+        };
+        let (field, value) = (field.trim(), value.trim());
+        if field.is_empty() || value.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Condition {
+            rule: rule.to_string(),
+            field: field.to_string(),
+            operator,
+            value: value.to_string(),
+        })
+    }
+}
+
+impl Condition {
+    // Parses rules separated by `;`.
+    pub fn parse_all(rules: &str) -> Result<Vec<Condition>> {
+        Ok(rules
+            .split(';')
+            .filter(|rule| !rule.trim().is_empty())
+            .map(Condition::from_str)
+            .collect::<Result<_, _>>()?)
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn matches(&self, item: &dyn Indexable) -> bool {
+        let actual = item.index(&self.field);
+        if self.operator == Operator::Contains {
+            return actual.to_lowercase().contains(&self.value.to_lowercase());
+        }
+
+        let ordering = match (parse_number(actual), self.value.parse::<f64>()) {
+            (Some(actual), Ok(expected)) => actual.partial_cmp(&expected),
+            _ => Some(actual.cmp(self.value.as_str())),
+        };
+        let ordering = match ordering {
+            Some(ordering) => ordering,
+            None => return false,
+        };
+
+        match self.operator {
+            Operator::Gt => ordering.is_gt(),
+            Operator::Ge => ordering.is_ge(),
+            Operator::Lt => ordering.is_lt(),
+            Operator::Le => ordering.is_le(),
+            Operator::Eq => ordering.is_eq(),
+            Operator::Ne => ordering.is_ne(),
+            Operator::Contains => unreachable!(),
+        }
+    }
+}
+
+// A number, or the upper bound of a range like `10-11`.
+fn parse_number(value: &str) -> Option<f64> {
+    if let Ok(number) = value.parse() {
+        return Some(number);
+    }
+    // The first char may be the sign of the lower bound.
+    let idx = value.get(1..)?.find('-')? + 1;
+    let (lower, upper) = (value[..idx].parse::<f64>(), value[idx + 1..].parse::<f64>());
+    match (lower, upper) {
+        (Ok(lower), Ok(upper)) => Some(lower.max(upper)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_condition {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Item(HashMap<&'static str, &'static str>);
+
+    impl<'a> std::ops::Index<&'a str> for Item {
+        type Output = str;
+        fn index(&self, field: &'a str) -> &Self::Output {
+            self.0.get(field).copied().unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let item = Item(
+            vec![
+                ("temp_max", "36"),
+                ("text_day", "Light Rain"),
+                ("wind_scale_day", "4-5"),
+                ("wind_scale_night", "10-11"),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let matches = |rule: &str| rule.parse::<Condition>().unwrap().matches(&item);
+
+        assert!(matches("temp_max > 35"));
+        assert!(!matches("temp_max >= 37"));
+        assert!(matches("text_day contains rain"));
+        assert!(matches("text_day == Light Rain"));
+        assert!(!matches("wind_scale_day >= 6"));
+        assert!(matches("wind_scale_night >= 6"));
+        assert!(matches("wind_scale_night>=11"));
+        assert!(!matches("wind_scale_night > 11"));
+        assert!(matches("temp_max>35"));
+        assert!(Condition::parse_all("temp_max ~ 3").is_err());
+        assert!(Condition::parse_all("temp_max >").is_err());
+        assert_eq!(Condition::parse_all("a > 1; b < 2;").unwrap().len(), 2);
+    }
+}
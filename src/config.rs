@@ -1,19 +1,54 @@
-use crate::mapper::TextMapper;
+use crate::atom::AtomFeed;
+use crate::bark::{self, BarkConfig, BarkSink};
+use crate::condition::Condition;
+use crate::dingtalk::{DingTalkConfig, DingTalkSink};
+use crate::discord::{DiscordConfig, DiscordSink};
+use crate::exec::{self, ExecConfig, ExecInput, ExecSink};
+use crate::feishu::{FeishuConfig, FeishuSink};
+use crate::file::{FileConfig, FileMode, FileSink, Framing, StdoutSink};
+use crate::github::{self, GitHubIssueConfig, GitHubIssueSink};
+use crate::gotify::{GotifyConfig, GotifySink};
+use crate::html::{self, FieldSelector, HtmlConfig, HtmlFeed};
+use crate::http_json::{HttpJsonConfig, HttpJsonFeed};
+use crate::ifttt::{self, IftttConfig, IftttSink};
+use crate::jsonfeed::JsonFeed;
+use crate::mapper::{JsonMapper, TextMapper};
+use crate::matrix::{MatrixConfig, MatrixSink, MsgType};
+use crate::mqtt::{self, MqttConfig, MqttSink};
+use crate::ntfy::{self, NtfyConfig, NtfySink};
+use crate::page_change::PageChangeFeed;
+use crate::pushover::{self, PushoverConfig, PushoverSink};
 use crate::rss::RssFeed;
-use crate::weather::WeatherFeed;
-use crate::web::WebSink;
-use crate::{ActionConfigs, ActionRun, Feeds, Mappers, Sinks, State};
+use crate::seen::DEFAULT_RETENTION;
+use crate::serverchan::{self, ServerChanConfig, ServerChanSink};
+use crate::slack::{SlackConfig, SlackSink};
+use crate::smtp::{self, SmtpConfig, SmtpSecurity, SmtpSink};
+use crate::telegram::{self, TelegramConfig, TelegramSink};
+use crate::template::Template;
+use crate::weather::{self, WeatherConfig, WeatherFeed};
+use crate::weather_provider::WeatherProvider;
+use crate::web::{self, MessageFormat, WebConfig, WebSink};
+use crate::wecom::{WeComConfig, WeComSink};
+use crate::{ActionConfigs, ActionRun, Feeds, Mapper, Mappers, Sink, Sinks, State};
 use anyhow::{Error, Result};
-use serde::{export::Formatter, export::TryFrom, Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::fmt::Formatter;
 use std::str::FromStr;
+use std::time::Duration;
 
 type CustomConfig<'a> = HashMap<&'a str, String>;
 
+// The default template of sinks which send a title along with the text.
+const DEFAULT_TITLE: &str = "{title}";
+
 #[derive(Debug)]
 pub enum ConfigError {
     NoConfigKey(&'static str),
+    UnsupportedValue(String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -45,7 +80,29 @@ pub struct ActionConfig<'a> {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KindAndConfig<'a> {
     kind: &'a str,
+    #[serde(borrow, deserialize_with = "deserialize_custom_config")]
     config: CustomConfig<'a>,
+    // The key of the action this belongs to.
+    #[serde(skip)]
+    action: &'a str,
+}
+
+// Values other than strings, such as the template of the json mapper, are read
+// from their JSON text.
+fn deserialize_custom_config<'de, D>(deserializer: D) -> Result<CustomConfig<'de>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let config: HashMap<&'de str, Value> = HashMap::deserialize(deserializer)?;
+    let config = config
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => (key, value),
+            value => (key, value.to_string()),
+        })
+        .collect();
+
+    Ok(config)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -63,7 +120,7 @@ impl TryFrom<Config<'_>> for Vec<ActionRun<Feeds, Mappers, Sinks>> {
         let mut states = match config.parameters.read_states() {
             Ok(states) => states,
             Err(_) => {
-                println!("No states is found. Use empty.");
+                eprintln!("No states is found. Use empty.");
                 HashMap::new()
             }
         };
@@ -78,10 +135,14 @@ impl TryFrom<Config<'_>> for Vec<ActionRun<Feeds, Mappers, Sinks>> {
 }
 
 impl ActionConfig<'_> {
-    pub fn into_action(self, state: State) -> Result<ActionRun<Feeds, Mappers, Sinks>> {
+    pub fn into_action(mut self, state: State) -> Result<ActionRun<Feeds, Mappers, Sinks>> {
+        self.sink.action = self.key;
         let feed: Feeds = self.feed.try_into()?;
-        let mapper: Mappers = self.mapper.try_into()?;
+        let mut mapper: Mappers = self.mapper.try_into()?;
         let sink: Sinks = self.sink.try_into()?;
+        if let Some(escape) = sink.value_escape() {
+            mapper.escape_values(escape);
+        }
 
         let config = if let Some(schedule) = self.config.get("schedule") {
             ActionConfigs::new(schedule.clone())
@@ -100,13 +161,75 @@ impl TryFrom<KindAndConfig<'_>> for Feeds {
             "rss" => RssFeed::new(
                 config.read_val::<String, _>("url")?,
                 config.read_val("count")?,
+                config.read_val_or("retention", DEFAULT_RETENTION)?,
+            )
+            .into(),
+            "atom" => AtomFeed::new(
+                config.read_val::<String, _>("url")?,
+                config.read_val("count")?,
+                config.read_val_or("retention", DEFAULT_RETENTION)?,
             )
             .into(),
-            "weather" => WeatherFeed::new(
-                config.read_val::<String, _>("key")?,
-                config.read_val::<String, _>("location")?,
+            "jsonfeed" => JsonFeed::new(
+                config.read_val::<String, _>("url")?,
+                config.read_val("count")?,
+                config.read_val_or("retention", DEFAULT_RETENTION)?,
             )
             .into(),
+            "json" => HttpJsonFeed::new(HttpJsonConfig {
+                url: config.read_val("url")?,
+                method: config.read_val_or("method", "GET".to_string())?,
+                headers: config.read_prefixed("header."),
+                query: config.read_prefixed("query."),
+                body: config.config.get("body").cloned(),
+                items: config.read_val_or("items", "$".to_string())?,
+                id: config.config.get("id").cloned(),
+                count: config.read_val_or("count", usize::MAX)?,
+                retention: config.read_val_or("retention", DEFAULT_RETENTION)?,
+            })
+            .into(),
+            "html" => {
+                let fields = config
+                    .read_prefixed("field.")
+                    .into_iter()
+                    .map(|(name, expr)| Ok((name, FieldSelector::parse(&expr)?)))
+                    .collect::<Result<_>>()?;
+                HtmlFeed::new(HtmlConfig {
+                    url: config.read_val("url")?,
+                    selector: html::parse_selector(&config.read_val::<String, _>("selector")?)?,
+                    fields,
+                    id: config.config.get("id").cloned(),
+                    count: config.read_val_or("count", usize::MAX)?,
+                    retention: config.read_val_or("retention", DEFAULT_RETENTION)?,
+                })
+                .into()
+            }
+            "page_change" => PageChangeFeed::new(
+                config.read_val::<String, _>("url")?,
+                config.config.get("selector").map(String::as_str),
+            )?
+            .into(),
+            "weather" => {
+                let provider: WeatherProvider =
+                    config.read_val_or("provider", WeatherProvider::HeWeather)?;
+                let key = if provider.needs_key() {
+                    config.read_val("key")?
+                } else {
+                    config.read_val_or("key", String::new())?
+                };
+                WeatherFeed::new(WeatherConfig {
+                    key,
+                    location: config.read_val("location")?,
+                    mode: config.read_val_or("mode", weather::DEFAULT_MODE.to_string())?,
+                    provider,
+                    base_url: config
+                        .read_val_or("base_url", provider.default_base_url().to_string())?,
+                    conditions: Condition::parse_all(
+                        &config.read_val_or("conditions", String::new())?,
+                    )?,
+                })
+                .into()
+            }
             _ => unimplemented!(),
         };
 
@@ -118,7 +241,8 @@ impl TryFrom<KindAndConfig<'_>> for Mappers {
     type Error = Error;
     fn try_from(config: KindAndConfig<'_>) -> Result<Self> {
         let res = match config.kind {
-            "text" => TextMapper::new(config.read_val::<String, _>("text")?).into(),
+            "text" => TextMapper::new(config.read_val("text")?).into(),
+            "json" => JsonMapper::new(config.read_val("template")?).into(),
             _ => unimplemented!(),
         };
 
@@ -130,11 +254,262 @@ impl TryFrom<KindAndConfig<'_>> for Sinks {
     type Error = Error;
     fn try_from(config: KindAndConfig<'_>) -> Result<Self> {
         let res = match config.kind {
-            "web" => WebSink::new(
-                config.read_val::<String, _>("method")?,
-                config.read_val::<String, _>("url")?,
-            )
+            "web" => {
+                let template_url = config.read_val_or("template_url", false)?;
+                let template = |text: String| -> Result<Template> {
+                    if template_url {
+                        Ok(text.parse()?)
+                    } else {
+                        Ok(Template::literal(text))
+                    }
+                };
+                WebSink::new(WebConfig {
+                    method: config.read_val("method")?,
+                    url: template(config.read_val("url")?)?,
+                    headers: config.read_prefixed("header."),
+                    query: config
+                        .read_prefixed("query.")
+                        .into_iter()
+                        .map(|(name, value)| Ok((name, template(value)?)))
+                        .collect::<Result<_>>()?,
+                    content_type: config.config.get("content_type").cloned(),
+                    bearer_token: config.config.get("bearer_token").cloned(),
+                    basic_auth: config
+                        .config
+                        .get("basic_auth")
+                        .map(|auth| web::parse_basic_auth(auth)),
+                    expected_status: config
+                        .read_list("expected_status")
+                        .iter()
+                        .map(|status| status.parse())
+                        .collect::<Result<_, _>>()?,
+                    assert_body_contains: config.config.get("assert_body_contains").cloned(),
+                    assert_json: config
+                        .config
+                        .get("assert_json")
+                        .map(|assertion| web::parse_json_assertion(assertion)),
+                })
+                .into()
+            }
+            "telegram" => TelegramSink::new(TelegramConfig {
+                api_url: config.read_val_or("api_url", telegram::TELEGRAM_API_URL.to_string())?,
+                bot_token: config.read_val("bot_token")?,
+                chat_id: config.read_val("chat_id")?,
+                parse_mode: config
+                    .config
+                    .get("parse_mode")
+                    .map(|mode| mode.parse())
+                    .transpose()?,
+                disable_preview: config.read_val_or("disable_preview", false)?,
+                silent: config.read_val_or("silent", false)?,
+            })
+            .into(),
+            "slack" => SlackSink::new(SlackConfig {
+                webhook_url: config.read_val("webhook_url")?,
+                username: config.config.get("username").cloned(),
+                icon_emoji: config.config.get("icon_emoji").cloned(),
+                icon_url: config.config.get("icon_url").cloned(),
+                blocks: config.read_val_or("blocks", false)?,
+            })
+            .into(),
+            "discord" => DiscordSink::new(DiscordConfig {
+                webhook_url: config.read_val("webhook_url")?,
+                username: config.config.get("username").cloned(),
+                avatar_url: config.config.get("avatar_url").cloned(),
+                embed: config.read_val_or("embed", false)?,
+            })
+            .into(),
+            "smtp" => {
+                let security: SmtpSecurity =
+                    config.read_val_or("security", SmtpSecurity::StartTls)?;
+                let credentials = match config.config.get("username") {
+                    Some(username) => Some((username.clone(), config.read_val("password")?)),
+                    None => None,
+                };
+                // A blank list is as good as a missing one.
+                let to = config.read_list("to");
+                if to.is_empty() {
+                    return Err(ConfigError::NoConfigKey("to").into());
+                }
+                SmtpSink::new(SmtpConfig {
+                    host: config.read_val("host")?,
+                    port: config.read_val_or("port", security.default_port())?,
+                    security,
+                    credentials,
+                    from: config.read_val("from")?,
+                    to: smtp::parse_mailboxes(&to)?,
+                    cc: smtp::parse_mailboxes(&config.read_list("cc"))?,
+                    subject: config.read_val_or("subject", DEFAULT_TITLE.parse()?)?,
+                    html: config.read_val_or("html", false)?,
+                })
+                .into()
+            }
+            "dingtalk" => DingTalkSink::new(DingTalkConfig {
+                webhook_url: config.read_val("webhook_url")?,
+                secret: config.config.get("secret").cloned(),
+                format: config.read_val_or("format", MessageFormat::Text)?,
+                title: config.read_val_or("title", DEFAULT_TITLE.parse()?)?,
+            })
+            .into(),
+            "wecom" => WeComSink::new(WeComConfig {
+                webhook_url: config.read_val("webhook_url")?,
+                format: config.read_val_or("format", MessageFormat::Text)?,
+            })
+            .into(),
+            "feishu" => FeishuSink::new(FeishuConfig {
+                webhook_url: config.read_val("webhook_url")?,
+                secret: config.config.get("secret").cloned(),
+                format: config.read_val_or("format", MessageFormat::Text)?,
+            })
+            .into(),
+            "serverchan" => ServerChanSink::new(ServerChanConfig {
+                api_url: config
+                    .read_val_or("api_url", serverchan::SERVERCHAN_API_URL.to_string())?,
+                send_key: config.read_val("send_key")?,
+                title: config.read_val_or("title", DEFAULT_TITLE.parse()?)?,
+            })
+            .into(),
+            "bark" => BarkSink::new(BarkConfig {
+                server: config.read_val_or("server", bark::BARK_SERVER.to_string())?,
+                device_key: config.read_val("device_key")?,
+                title: config.read_val_or("title", DEFAULT_TITLE.parse()?)?,
+                format: config.read_val_or("format", MessageFormat::Text)?,
+                group: config.config.get("group").cloned(),
+                sound: config.config.get("sound").cloned(),
+                url: config.read_val_opt("url")?,
+            })
+            .into(),
+            "ntfy" => NtfySink::new(NtfyConfig {
+                server: config.read_val_or("server", ntfy::NTFY_SERVER.to_string())?,
+                topic: config.read_val("topic")?,
+                token: config.config.get("token").cloned(),
+                title: config.read_val_opt("title")?,
+                click: config.read_val_opt("click")?,
+                priority: config.read_val_opt("priority")?,
+                tags: config.read_list("tags"),
+            })
+            .into(),
+            "gotify" => GotifySink::new(GotifyConfig {
+                server: config.read_val("server")?,
+                app_token: config.read_val("app_token")?,
+                title: config.read_val_opt("title")?,
+                priority: config
+                    .config
+                    .get("priority")
+                    .map(|p| p.parse())
+                    .transpose()?,
+            })
+            .into(),
+            "pushover" => PushoverSink::new(PushoverConfig {
+                api_url: config.read_val_or("api_url", pushover::PUSHOVER_API_URL.to_string())?,
+                app_key: config.read_val("app_key")?,
+                user_key: config.read_val("user_key")?,
+                title: config.read_val_opt("title")?,
+                url: config.read_val_opt("url")?,
+                sound: config.config.get("sound").cloned(),
+                priority: config
+                    .config
+                    .get("priority")
+                    .map(|p| p.parse())
+                    .transpose()?,
+            })
+            .into(),
+            "matrix" => MatrixSink::new(MatrixConfig {
+                homeserver: config.read_val("homeserver")?,
+                room_id: config.read_val("room_id")?,
+                access_token: config.read_val("access_token")?,
+                msgtype: config.read_val_or("msgtype", MsgType::Text)?,
+            })
+            .into(),
+            "stdout" => StdoutSink::new(config.read_framing()?).into(),
+            "file" => FileSink::new(FileConfig {
+                path: config.read_val::<String, _>("path")?.into(),
+                mode: config.read_val_or("mode", FileMode::Append)?,
+                framing: config.read_framing()?,
+                max_size: config
+                    .config
+                    .get("max_size")
+                    .map(|s| s.parse())
+                    .transpose()?,
+            })
+            .into(),
+            "exec" => {
+                let mut args = config
+                    .read_prefixed("arg.")
+                    .into_iter()
+                    .map(|(idx, arg)| Ok((idx.parse::<usize>()?, arg.parse()?)))
+                    .collect::<Result<Vec<_>>>()?;
+                args.sort_by_key(|(idx, _)| *idx);
+                let input = match config.read_val_or("input", ExecInput::Stdin)? {
+                    ExecInput::Env(_) => ExecInput::Env(
+                        config.read_val_or("env_name", exec::DEFAULT_ENV_NAME.to_string())?,
+                    ),
+                    input => input,
+                };
+                ExecSink::new(ExecConfig {
+                    program: config.read_val("program")?,
+                    args: args.into_iter().map(|(_, arg)| arg).collect(),
+                    input,
+                    timeout: Duration::from_secs(
+                        config.read_val_or("timeout", exec::DEFAULT_TIMEOUT)?,
+                    ),
+                })
+                .into()
+            }
+            "github_issue" => GitHubIssueSink::new(GitHubIssueConfig {
+                api_url: config.read_val_or("api_url", github::GITHUB_API_URL.to_string())?,
+                token: config.read_val("token")?,
+                repo: config.read_val("repo")?,
+                title: config.read_val_or("title", DEFAULT_TITLE.parse()?)?,
+                labels: config.read_list("labels"),
+                assignees: config.read_list("assignees"),
+                issue_number: config
+                    .config
+                    .get("issue_number")
+                    .map(|number| number.parse())
+                    .transpose()?,
+                dedupe: config.read_val_or("dedupe", false)?,
+            })
             .into(),
+            "ifttt" => IftttSink::new(IftttConfig {
+                base_url: config.read_val_or("base_url", ifttt::IFTTT_URL.to_string())?,
+                event: config.read_val("event")?,
+                key: config.read_val("key")?,
+                value1: config.read_val_opt("value1")?,
+                value2: config.read_val_opt("value2")?,
+                value3: config.read_val_opt("value3")?,
+            })
+            .into(),
+            "mqtt" => {
+                let tls = config.read_val_or("tls", false)?;
+                let default_port = if tls {
+                    mqtt::DEFAULT_TLS_PORT
+                } else {
+                    mqtt::DEFAULT_PORT
+                };
+                let credentials = match config.config.get("username") {
+                    Some(username) => Some((
+                        username.clone(),
+                        config.read_val_or("password", String::new())?,
+                    )),
+                    None => None,
+                };
+                MqttSink::new(MqttConfig {
+                    host: config.read_val("host")?,
+                    port: config.read_val_or("port", default_port)?,
+                    client_id: config
+                        .read_val_or("client_id", mqtt::default_client_id(config.action))?,
+                    credentials,
+                    tls,
+                    topic: config.read_val("topic")?,
+                    qos: config.read_val_or("qos", mqtt::DEFAULT_QOS)?,
+                    retain: config.read_val_or("retain", false)?,
+                    timeout: Duration::from_secs(
+                        config.read_val_or("timeout", mqtt::DEFAULT_TIMEOUT)?,
+                    ),
+                })
+                .into()
+            }
             _ => unimplemented!(),
         };
 
@@ -148,18 +523,74 @@ impl KindAndConfig<'_> {
         T: FromStr<Err = TE>,
         TE: std::error::Error + Send + Sync + 'static,
     {
-        let value = self
-            .config
-            .get(key)
-            .ok_or_else(|| ConfigError::NoConfigKey(key))?;
+        let value = self.config.get(key).ok_or(ConfigError::NoConfigKey(key))?;
 
         Ok(T::from_str(value)?)
     }
+
+    pub fn read_framing(&self) -> Result<Framing> {
+        Ok(Framing {
+            action: self.action.to_string(),
+            json_lines: self.read_val_or("json_lines", false)?,
+        })
+    }
+
+    // Reads a comma separated list, which is empty if the key is absent.
+    pub fn read_list(&self, key: &str) -> Vec<String> {
+        self.config
+            .get(key)
+            .map(|list| {
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Collects entries like `header.Accept` as `("Accept", value)`.
+    pub fn read_prefixed(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut values: Vec<_> = self
+            .config
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(prefix)
+                    .map(|name| (name.to_string(), value.clone()))
+            })
+            .collect();
+        values.sort();
+
+        values
+    }
+
+    pub fn read_val_opt<T, TE>(&self, key: &'static str) -> Result<Option<T>>
+    where
+        T: FromStr<Err = TE>,
+        TE: std::error::Error + Send + Sync + 'static,
+    {
+        match self.config.get(key) {
+            Some(value) => Ok(Some(T::from_str(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn read_val_or<T, TE>(&self, key: &'static str, default: T) -> Result<T>
+    where
+        T: FromStr<Err = TE>,
+        TE: std::error::Error + Send + Sync + 'static,
+    {
+        match self.config.get(key) {
+            Some(value) => Ok(T::from_str(value)?),
+            None => Ok(default),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test_config {
     use super::*;
+    use crate::test_server::TestItem;
 
     #[test]
     fn test_deserialize() {
@@ -219,4 +650,101 @@ mod test_config {
         let config: Config = serde_json::from_str(config).unwrap();
         dbg!(config);
     }
+
+    fn kind_and_config<'a>(kind: &'a str, config: &[(&'a str, &str)]) -> KindAndConfig<'a> {
+        KindAndConfig {
+            kind,
+            config: config
+                .iter()
+                .map(|(key, value)| (*key, value.to_string()))
+                .collect(),
+            action: "action",
+        }
+    }
+
+    #[test]
+    fn test_weather_key() {
+        let heweather = kind_and_config("weather", &[("location", "101010100")]);
+        let err = Feeds::try_from(heweather).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(ConfigError::NoConfigKey("key"))
+        ));
+
+        let open_meteo = kind_and_config(
+            "weather",
+            &[("location", "52.52,13.41"), ("provider", "open_meteo")],
+        );
+        assert!(Feeds::try_from(open_meteo).is_ok());
+    }
+
+    #[test]
+    fn test_smtp_recipients() {
+        for to in &[None, Some(" , ")] {
+            let mut entries = vec![("host", "smtp.example.com"), ("from", "bot@example.com")];
+            if let Some(to) = to {
+                entries.push(("to", to));
+            }
+            let err = Sinks::try_from(kind_and_config("smtp", &entries))
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err.downcast_ref(),
+                Some(ConfigError::NoConfigKey("to"))
+            ));
+        }
+
+        let smtp = kind_and_config(
+            "smtp",
+            &[
+                ("host", "smtp.example.com"),
+                ("from", "bot@example.com"),
+                ("to", "alice@example.com, bob@example.com"),
+            ],
+        );
+        assert!(Sinks::try_from(smtp).is_ok());
+    }
+
+    #[test]
+    fn test_ntfy_priority() {
+        let ntfy = kind_and_config("ntfy", &[("topic", "news"), ("priority", "urgent")]);
+        let err = Sinks::try_from(ntfy).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(ConfigError::UnsupportedValue(priority)) if priority == "urgent"
+        ));
+
+        let ntfy = kind_and_config("ntfy", &[("topic", "news"), ("priority", "max")]);
+        assert!(Sinks::try_from(ntfy).is_ok());
+    }
+    #[test]
+    fn test_json_template() {
+        let config = r#"
+        {
+            "actions": [
+                {
+                    "key": "Index",
+                    "feed": {"kind": "rss", "config": {"url": "rss link", "count": 10}},
+                    "mapper": {
+                        "kind": "json",
+                        "config": {
+                            "template": {"text": "{title}", "tags": ["news", "{author}"], "silent": true}
+                        }
+                    },
+                    "sink": {"kind": "stdout", "config": {}}
+                }
+            ],
+            "parameters": {}
+        }
+        "#;
+
+        let config: Config = serde_json::from_str(config).unwrap();
+        let action = config.actions.into_iter().next().unwrap();
+        let action = action.into_action(State::new()).unwrap();
+        let item = TestItem::new(&[("title", "Q&A"), ("author", "Alice")]);
+        assert_eq!(
+            action.mapper.map(&item).unwrap(),
+            r#"{"text":"Q&A","tags":["news","Alice"],"silent":true}"#
+        );
+    }
 }
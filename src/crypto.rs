@@ -2,8 +2,10 @@ use crate::config::Parameters;
 use crate::States;
 use anyhow::anyhow;
 use anyhow::Result;
+use base64::Engine;
 use lazy_static::lazy_static;
 use ring::aead::{Aad, Algorithm, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
 use ring::rand::{SecureRandom, SystemRandom};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
@@ -94,3 +96,11 @@ fn new_nonce() -> [u8; NONCE_LEN] {
 
     nonce
 }
+
+// The base64 encoded HMAC-SHA256 of a message, which webhooks use as signature.
+pub(crate) fn hmac_sha256_base64(key: &[u8], message: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    let tag = hmac::sign(&key, message);
+
+    base64::engine::general_purpose::STANDARD.encode(tag.as_ref())
+}
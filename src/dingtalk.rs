@@ -0,0 +1,112 @@
+use crate::crypto::hmac_sha256_base64;
+use crate::template::Template;
+use crate::web::{check_api_code, ensure_success, MessageFormat};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
+
+// Posts to a DingTalk group robot.
+pub struct DingTalkSink {
+    config: DingTalkConfig,
+}
+
+pub struct DingTalkConfig {
+    // The full webhook url including the access token.
+    pub webhook_url: String,
+    // Robots with the signature security setting need the secret to sign requests.
+    pub secret: Option<String>,
+    pub format: MessageFormat,
+    // The markdown title shown in the conversation list.
+    pub title: Template,
+}
+
+impl DingTalkSink {
+    pub fn new(config: DingTalkConfig) -> Self {
+        DingTalkSink { config }
+    }
+}
+
+// https://open.dingtalk.com/document/robots/customize-robot-security-settings
+fn sign(secret: &str, timestamp: i64) -> String {
+    let message = format!("{}\n{}", timestamp, secret);
+    hmac_sha256_base64(secret.as_bytes(), message.as_bytes())
+}
+
+#[async_trait(?Send)]
+impl Sink for DingTalkSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let payload = match config.format {
+            MessageFormat::Text => json!({"msgtype": "text", "text": { "content": input }}),
+            MessageFormat::Markdown => json!({
+                "msgtype": "markdown",
+                "markdown": {
+                    "title": config.title.render(item),
+                    "text": input,
+                },
+            }),
+        };
+
+        let client = reqwest::Client::new();
+        let mut request = client.post(&config.webhook_url).json(&payload);
+        if let Some(ref secret) = config.secret {
+            let timestamp = Utc::now().timestamp_millis();
+            request = request.query(&[
+                ("timestamp", timestamp.to_string()),
+                ("sign", sign(secret, timestamp)),
+            ]);
+        }
+
+        let body = ensure_success(request.send().await?).await?;
+        check_api_code("DingTalk", &body, "errcode", "0", "errmsg")
+    }
+}
+
+#[cfg(test)]
+mod test_dingtalk {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use crate::web::ApiError;
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("SEC000", 1577836800000),
+            "sLtiQUMv1vBmuenplUukSZ+QlhX/gyh/F0ARoP5z+TM="
+        );
+    }
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"errcode": 0, "errmsg": "ok"}"#),
+            TestResponse::new(200, r#"{"errcode": 310000, "errmsg": "sign not match"}"#),
+        ]);
+        let sink = DingTalkSink::new(DingTalkConfig {
+            webhook_url: format!("{}/robot/send?access_token=abc", server.url),
+            secret: Some("SEC000".to_string()),
+            format: MessageFormat::Markdown,
+            title: "{title}".parse().unwrap(),
+        });
+        let item = TestItem::new(&[("title", "News")]);
+
+        task::block_on(sink.sink("**hi**".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert!(request
+            .path
+            .starts_with("/robot/send?access_token=abc&timestamp="));
+        assert!(request.path.contains("&sign="));
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(payload["markdown"]["title"], "News");
+        assert_eq!(payload["markdown"]["text"], "**hi**");
+
+        let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+        let err = err.downcast::<ApiError>().unwrap();
+        assert_eq!(err.code, "310000");
+        assert_eq!(err.message, "sign not match");
+    }
+}
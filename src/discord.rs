@@ -0,0 +1,99 @@
+use crate::web::{ensure_success, send_with_retry, split_text};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+const CONTENT_LIMIT: usize = 2000;
+const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+
+// Posts to a Discord webhook.
+pub struct DiscordSink {
+    config: DiscordConfig,
+}
+
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    pub username: Option<String>,
+    pub avatar_url: Option<String>,
+    // Sends the text as the description of an embed rather than as `content`.
+    pub embed: bool,
+}
+
+impl DiscordSink {
+    pub fn new(config: DiscordConfig) -> Self {
+        DiscordSink { config }
+    }
+
+    fn payloads(&self, input: &str) -> Vec<Value> {
+        let config = &self.config;
+        let limit = if config.embed {
+            EMBED_DESCRIPTION_LIMIT
+        } else {
+            CONTENT_LIMIT
+        };
+
+        split_text(input, limit)
+            .into_iter()
+            .map(|text| {
+                let mut payload = if config.embed {
+                    json!({"embeds": [{ "description": text }]})
+                } else {
+                    json!({ "content": text })
+                };
+                if let Some(ref username) = config.username {
+                    payload["username"] = username.as_str().into();
+                }
+                if let Some(ref avatar_url) = config.avatar_url {
+                    payload["avatar_url"] = avatar_url.as_str().into();
+                }
+                payload
+            })
+            .collect()
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for DiscordSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let client = reqwest::Client::new();
+        for payload in self.payloads(&input) {
+            let request = client.post(&self.config.webhook_url).json(&payload);
+            ensure_success(send_with_retry(request).await?).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_discord {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(429, r#"{"retry_after": 0.01, "global": false}"#),
+            TestResponse::new(204, ""),
+            TestResponse::new(204, ""),
+        ]);
+        let sink = DiscordSink::new(DiscordConfig {
+            webhook_url: format!("{}/api/webhooks/1/token", server.url),
+            username: Some("bot".to_string()),
+            avatar_url: None,
+            embed: false,
+        });
+        let input = format!("\"{}\"", "a".repeat(CONTENT_LIMIT));
+
+        task::block_on(sink.sink(input, &TestItem::new(&[]))).unwrap();
+
+        server.request();
+        let first: Value = serde_json::from_str(&server.request().body).unwrap();
+        let second: Value = serde_json::from_str(&server.request().body).unwrap();
+        assert_eq!(first["username"], "bot");
+        assert_eq!(first["content"].as_str().unwrap().len(), CONTENT_LIMIT);
+        assert_eq!(second["content"], "a\"");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::config::ConfigError;
+use crate::template::Template;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_std::task;
+use async_trait::async_trait;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_ENV_NAME: &str = "IFTTT_TEXT";
+pub const DEFAULT_TIMEOUT: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Runs a program for every mapped item.
+pub struct ExecSink {
+    config: ExecConfig,
+}
+
+pub struct ExecConfig {
+    pub program: String,
+    pub args: Vec<Template>,
+    pub input: ExecInput,
+    pub timeout: Duration,
+}
+
+// How the mapped text reaches the program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecInput {
+    Stdin,
+    Env(String),
+}
+
+impl FromStr for ExecInput {
+    type Err = ConfigError;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "stdin" => Ok(ExecInput::Stdin),
+            "env" => Ok(ExecInput::Env(DEFAULT_ENV_NAME.to_string())),
+            _ => Err(ConfigError::UnsupportedValue(input.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExecError {
+    Timeout(Duration),
+    Failed { status: ExitStatus, stderr: String },
+}
+
+impl Display for ExecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::Timeout(timeout) => write!(f, "Killed after {:?}.", timeout),
+            ExecError::Failed { status, stderr } => write!(f, "{}: {}", status, stderr.trim()),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+impl ExecSink {
+    pub fn new(config: ExecConfig) -> Self {
+        ExecSink { config }
+    }
+}
+
+fn run(mut command: Command, stdin: Option<String>, timeout: Duration) -> Result<()> {
+    let mut child = command.spawn()?;
+
+    // Both pipes are served from threads, so a chatty program can't block on them.
+    let writer = child.stdin.take().map(|mut pipe| {
+        let input = stdin.unwrap_or_default();
+        thread::spawn(move || pipe.write_all(input.as_bytes()))
+    });
+    let reader = child.stderr.take().map(|mut pipe| {
+        thread::spawn(move || {
+            let mut stderr = String::new();
+            pipe.read_to_string(&mut stderr).map(|_| stderr)
+        })
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            return Err(ExecError::Timeout(timeout).into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    if let Some(writer) = writer {
+        // The program may exit without reading its input, which isn't an error.
+        let _ = writer.join();
+    }
+    let stderr = match reader {
+        Some(reader) => reader.join().unwrap_or_else(|_| Ok(String::new()))?,
+        None => String::new(),
+    };
+
+    if !status.success() {
+        return Err(ExecError::Failed { status, stderr }.into());
+    }
+
+    Ok(())
+}
+
+#[async_trait(?Send)]
+impl Sink for ExecSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let mut command = Command::new(&config.program);
+        for arg in config.args.iter() {
+            command.arg(arg.render(item));
+        }
+        command.stdout(Stdio::null()).stderr(Stdio::piped());
+
+        let stdin = match config.input {
+            ExecInput::Stdin => {
+                command.stdin(Stdio::piped());
+                Some(input)
+            }
+            ExecInput::Env(ref name) => {
+                command.stdin(Stdio::null()).env(name, input);
+                None
+            }
+        };
+
+        let timeout = config.timeout;
+        task::spawn_blocking(move || run(command, stdin, timeout)).await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test_exec {
+    use super::*;
+    use crate::test_server::TestItem;
+
+    fn sink(args: &[&str], input: ExecInput, timeout: u64) -> ExecSink {
+        ExecSink::new(ExecConfig {
+            program: "sh".to_string(),
+            args: args.iter().map(|arg| arg.parse().unwrap()).collect(),
+            input,
+            timeout: Duration::from_secs(timeout),
+        })
+    }
+
+    #[test]
+    fn test_sink() {
+        let item = TestItem::new(&[("title", "Release")]);
+        let run = |sink: ExecSink| task::block_on(sink.sink("hello".to_string(), &item));
+
+        let check = r#"test "$(cat)" = hello && test "$0" = Release"#;
+        run(sink(&["-c", check, "{title}"], ExecInput::Stdin, 5)).unwrap();
+
+        let check = r#"test "$TEXT" = hello"#;
+        run(sink(&["-c", check], ExecInput::Env("TEXT".to_string()), 5)).unwrap();
+
+        let err = run(sink(&["-c", "echo oops >&2; exit 3"], ExecInput::Stdin, 5)).unwrap_err();
+        match err.downcast::<ExecError>().unwrap() {
+            ExecError::Failed { status, stderr } => {
+                assert_eq!(status.code(), Some(3));
+                assert_eq!(stderr, "oops\n");
+            }
+            err => panic!("Unexpected error {:?}", err),
+        }
+
+        let err = run(sink(&["-c", "sleep 5"], ExecInput::Stdin, 0)).unwrap_err();
+        assert!(matches!(
+            err.downcast::<ExecError>().unwrap(),
+            ExecError::Timeout(_)
+        ));
+    }
+}
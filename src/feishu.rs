@@ -0,0 +1,107 @@
+use crate::crypto::hmac_sha256_base64;
+use crate::web::{check_api_code, ensure_success, MessageFormat};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
+
+// Posts to a Feishu (Lark) custom bot.
+pub struct FeishuSink {
+    config: FeishuConfig,
+}
+
+pub struct FeishuConfig {
+    pub webhook_url: String,
+    // Bots with signature verification need the secret to sign requests.
+    pub secret: Option<String>,
+    pub format: MessageFormat,
+}
+
+impl FeishuSink {
+    pub fn new(config: FeishuConfig) -> Self {
+        FeishuSink { config }
+    }
+}
+
+// Feishu signs an empty message, keyed by the timestamp and the secret.
+// https://open.feishu.cn/document/client-docs/bot-v3/add-custom-bot
+fn sign(secret: &str, timestamp: i64) -> String {
+    let key = format!("{}\n{}", timestamp, secret);
+    hmac_sha256_base64(key.as_bytes(), b"")
+}
+
+#[async_trait(?Send)]
+impl Sink for FeishuSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let mut payload = match config.format {
+            MessageFormat::Text => json!({"msg_type": "text", "content": { "text": input }}),
+            MessageFormat::Markdown => json!({
+                "msg_type": "interactive",
+                "card": {"elements": [{ "tag": "markdown", "content": input }]},
+            }),
+        };
+        if let Some(ref secret) = config.secret {
+            let timestamp = Utc::now().timestamp();
+            payload["timestamp"] = timestamp.to_string().into();
+            payload["sign"] = sign(secret, timestamp).into();
+        }
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&config.webhook_url)
+            .json(&payload)
+            .send()
+            .await?;
+
+        let body = ensure_success(res).await?;
+        check_api_code("Feishu", &body, "code", "0", "msg")
+    }
+}
+
+#[cfg(test)]
+mod test_feishu {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use crate::web::ApiError;
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("SEC000", 1599360473),
+            "lNrbbw+GOWJFN+1poob9duf10QaEkXZ3A4VDmGwBgxI="
+        );
+    }
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"code": 0, "msg": "success"}"#),
+            TestResponse::new(200, r#"{"code": 19021, "msg": "sign match fail"}"#),
+        ]);
+        let sink = FeishuSink::new(FeishuConfig {
+            webhook_url: format!("{}/open-apis/bot/v2/hook/abc", server.url),
+            secret: Some("SEC000".to_string()),
+            format: MessageFormat::Markdown,
+        });
+        let item = TestItem::new(&[]);
+
+        task::block_on(sink.sink("**hi**".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/open-apis/bot/v2/hook/abc");
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(payload["msg_type"], "interactive");
+        assert_eq!(payload["card"]["elements"][0]["content"], "**hi**");
+        let timestamp: i64 = payload["timestamp"].as_str().unwrap().parse().unwrap();
+        assert_eq!(payload["sign"], sign("SEC000", timestamp));
+
+        let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+        let err = err.downcast::<ApiError>().unwrap();
+        assert_eq!(err.code, "19021");
+        assert_eq!(err.message, "sign match fail");
+    }
+}
@@ -0,0 +1,175 @@
+use crate::config::ConfigError;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// How a mapped text is written out.
+pub struct Framing {
+    // The key of the action the text comes from.
+    pub action: String,
+    // Writes `{"action", "timestamp", "text"}` per line instead of the raw text.
+    pub json_lines: bool,
+}
+
+impl Framing {
+    fn frame(&self, input: String) -> String {
+        if !self.json_lines {
+            return input + "\n";
+        }
+
+        let line = json!({
+            "action": self.action,
+            "timestamp": Utc::now().to_rfc3339(),
+            "text": input,
+        });
+        line.to_string() + "\n"
+    }
+}
+
+// Prints to stdout, to debug templates or pipe `action_cli` into other tools.
+pub struct StdoutSink {
+    framing: Framing,
+}
+
+impl StdoutSink {
+    pub fn new(framing: Framing) -> Self {
+        StdoutSink { framing }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for StdoutSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(self.framing.frame(input).as_bytes())?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+}
+
+// Archives notifications into a local file.
+pub struct FileSink {
+    config: FileConfig,
+}
+
+pub struct FileConfig {
+    pub path: PathBuf,
+    pub mode: FileMode,
+    pub framing: Framing,
+    // Once the file reaches this many bytes it's moved to `<path>.1`.
+    pub max_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileMode {
+    Append,
+    // The file only keeps the latest text.
+    Overwrite,
+}
+
+impl FromStr for FileMode {
+    type Err = ConfigError;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "append" => Ok(FileMode::Append),
+            "overwrite" => Ok(FileMode::Overwrite),
+            _ => Err(ConfigError::UnsupportedValue(mode.to_string())),
+        }
+    }
+}
+
+impl FileSink {
+    pub fn new(config: FileConfig) -> Self {
+        FileSink { config }
+    }
+
+    fn rotate(&self) -> Result<()> {
+        let config = &self.config;
+        match (config.max_size, fs::metadata(&config.path)) {
+            (Some(max_size), Ok(metadata)) if metadata.len() >= max_size => (),
+            _ => return Ok(()),
+        }
+
+        let mut rotated = config.path.clone().into_os_string();
+        rotated.push(".1");
+        fs::rename(&config.path, &rotated)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for FileSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        self.rotate()?;
+
+        let append = config.mode == FileMode::Append;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&config.path)?;
+        file.write_all(config.framing.frame(input).as_bytes())?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_file {
+    use super::*;
+    use crate::test_server::TestItem;
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("ifttt-file-sink-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("archive.jsonl");
+        let sink = FileSink::new(FileConfig {
+            path: path.clone(),
+            mode: FileMode::Append,
+            framing: Framing {
+                action: "news".to_string(),
+                json_lines: true,
+            },
+            max_size: Some(100),
+        });
+        let item = TestItem::new(&[]);
+
+        for text in &["first", "second", "third"] {
+            task::block_on(sink.sink(text.to_string(), &item)).unwrap();
+        }
+
+        let rotated = fs::read_to_string(dir.join("archive.jsonl.1")).unwrap();
+        let current = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(rotated.lines().count(), 2);
+        let line: Value = serde_json::from_str(current.trim_end()).unwrap();
+        assert_eq!(line["action"], "news");
+        assert_eq!(line["text"], "third");
+    }
+
+    #[test]
+    fn test_mode() {
+        assert_eq!("append".parse::<FileMode>().unwrap(), FileMode::Append);
+        assert_eq!(
+            "overwrite".parse::<FileMode>().unwrap(),
+            FileMode::Overwrite
+        );
+        assert!("overwrite ".parse::<FileMode>().is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use chrono_tz::Tz;
+use scraper::Html;
+
+// The chars Telegram's MarkdownV2 requires to be escaped outside of entities.
+const MARKDOWN_V2_SPECIAL: &str = "_*[]()~`>#+-=|{}.!\\";
+
+const FILTERS: [&str; 9] = [
+    "truncate",
+    "strip_html",
+    "urlencode",
+    "json_escape",
+    "markdown_escape",
+    "html_escape",
+    "upper",
+    "lower",
+    "date",
+];
+
+// Transforms a value in a template, as in `{title | truncate(80) | upper}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    // At most this many chars, ending with `…` when cut.
+    Truncate(usize),
+    StripHtml,
+    UrlEncode,
+    // The value as the inside of a JSON string.
+    JsonEscape,
+    // For Telegram's MarkdownV2.
+    MarkdownEscape,
+    // For text inside HTML tags and attributes.
+    HtmlEscape,
+    Upper,
+    Lower,
+    // A strftime format, in the time zone if given or the date's own offset.
+    Date { format: String, tz: Option<Tz> },
+}
+
+impl Filter {
+    // The error is reported at the filter's position in the template.
+    pub fn new(name: &str, args: &[String]) -> Result<Self, String> {
+        let filter = match (name, args) {
+            ("truncate", [len]) => match len.parse() {
+                Ok(len) => Filter::Truncate(len),
+                Err(_) => return Err(format!("Invalid length `{}`", len)),
+            },
+            ("strip_html", []) => Filter::StripHtml,
+            ("urlencode", []) => Filter::UrlEncode,
+            ("json_escape", []) => Filter::JsonEscape,
+            ("markdown_escape", []) => Filter::MarkdownEscape,
+            ("html_escape", []) => Filter::HtmlEscape,
+            ("upper", []) => Filter::Upper,
+            ("lower", []) => Filter::Lower,
+            ("date", [format]) => Filter::date(format, None)?,
+            ("date", [format, tz]) => Filter::date(format, Some(tz))?,
+            _ if FILTERS.contains(&name) => return Err(format!("Wrong arguments for `{}`", name)),
+            _ => return Err(format!("Unknown filter `{}`", name)),
+        };
+
+        Ok(filter)
+    }
+
+    fn date(format: &str, tz: Option<&String>) -> Result<Self, String> {
+        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("Invalid date format `{}`", format));
+        }
+        let tz = match tz {
+            Some(tz) => Some(
+                tz.parse()
+                    .map_err(|_| format!("Unknown time zone `{}`", tz))?,
+            ),
+            None => None,
+        };
+
+        Ok(Filter::Date {
+            format: format.to_string(),
+            tz,
+        })
+    }
+
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Filter::Truncate(len) => truncate(value, *len),
+            Filter::StripHtml => Html::parse_fragment(value)
+                .root_element()
+                .text()
+                .collect::<String>()
+                .trim()
+                .to_string(),
+            Filter::UrlEncode => url_encode(value),
+            Filter::JsonEscape => {
+                let quoted = serde_json::Value::from(value).to_string();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            Filter::MarkdownEscape => markdown_escape(value),
+            Filter::HtmlEscape => html_escape(value),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Lower => value.to_lowercase(),
+            // Values which aren't dates are kept as they are.
+            Filter::Date { format, tz } => match (parse_date(value), tz) {
+                (Some(date), Some(tz)) => date.with_timezone(tz).format(format).to_string(),
+                (Some(date), None) => date.format(format).to_string(),
+                (None, _) => value.to_string(),
+            },
+        }
+    }
+}
+
+fn truncate(value: &str, len: usize) -> String {
+    if value.chars().count() <= len {
+        return value.to_string();
+    }
+    let mut truncated: String = value.chars().take(len.saturating_sub(1)).collect();
+    truncated.truncate(truncated.trim_end().len());
+    if len > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+fn markdown_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if MARKDOWN_V2_SPECIAL.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn html_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// RFC 3339 as in Atom and JSON Feed, RFC 2822 as in RSS, or a Unix timestamp.
+fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc2822(value))
+        .ok()
+        .or_else(|| {
+            let secs = value.parse().ok()?;
+            Some(Utc.timestamp_opt(secs, 0).single()?.into())
+        })
+}
+
+#[cfg(test)]
+mod test_filter {
+    use super::*;
+
+    fn apply(name: &str, args: &[&str], value: &str) -> String {
+        let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+        Filter::new(name, &args).unwrap().apply(value)
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(apply("truncate", &["6"], "Hello world"), "Hello…");
+        assert_eq!(apply("truncate", &["20"], "Hello world"), "Hello world");
+        assert_eq!(
+            apply("strip_html", &[], "<p>Fish &amp; <b>chips</b></p>"),
+            "Fish & chips"
+        );
+        assert_eq!(apply("urlencode", &[], "a b&c/é"), "a%20b%26c%2F%C3%A9");
+        assert_eq!(apply("json_escape", &[], "say \"hi\"\n"), r#"say \"hi\"\n"#);
+        assert_eq!(apply("markdown_escape", &[], "1.5*2"), r"1\.5\*2");
+        assert_eq!(apply("markdown_escape", &[], r"[a](b) \"), r"\[a\]\(b\) \\");
+        assert_eq!(
+            apply("html_escape", &[], r#"<a href="x">Q&A</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Q&amp;A&lt;/a&gt;"
+        );
+        assert_eq!(
+            apply(
+                "date",
+                &["%Y-%m-%d %H:%M", "Asia/Shanghai"],
+                "Mon, 01 Jun 2020 20:00:00 GMT"
+            ),
+            "2020-06-02 04:00"
+        );
+        assert_eq!(apply("date", &["%Y"], "not a date"), "not a date");
+    }
+
+    #[test]
+    fn test_new() {
+        let error = |name: &str, args: &[&str]| {
+            let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+            Filter::new(name, &args).unwrap_err()
+        };
+
+        assert_eq!(error("shout", &[]), "Unknown filter `shout`");
+        assert_eq!(error("truncate", &[]), "Wrong arguments for `truncate`");
+        assert_eq!(
+            error("date", &["%Y", "Mars/Olympus"]),
+            "Unknown time zone `Mars/Olympus`"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::template::Template;
+use crate::web::ensure_success;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{Client, Method, RequestBuilder};
+use serde::Deserialize;
+use serde_json::json;
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+// Opens an issue per item, or comments on an existing one.
+pub struct GitHubIssueSink {
+    config: GitHubIssueConfig,
+}
+
+pub struct GitHubIssueConfig {
+    pub api_url: String,
+    pub token: String,
+    // `owner/name`.
+    pub repo: String,
+    pub title: Template,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    // Comments on this issue instead of opening new ones.
+    pub issue_number: Option<u64>,
+    // Comments on an open issue with the same title if there's one.
+    pub dedupe: bool,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    items: Vec<Issue>,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+}
+
+impl GitHubIssueSink {
+    pub fn new(config: GitHubIssueConfig) -> Self {
+        GitHubIssueSink { config }
+    }
+
+    fn request(&self, client: &Client, method: Method, path: &str) -> RequestBuilder {
+        client
+            .request(method, &format!("{}{}", self.config.api_url, path))
+            .bearer_auth(&self.config.token)
+            .header(reqwest::header::USER_AGENT, "ifttt-action")
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+    }
+
+    async fn find_open_issue(&self, client: &Client, title: &str) -> Result<Option<u64>> {
+        let query = format!(
+            "repo:{} is:issue is:open in:title \"{}\"",
+            self.config.repo,
+            title.replace('"', " ")
+        );
+        let res = self
+            .request(client, Method::GET, "/search/issues")
+            .query(&[("q", query.as_str()), ("per_page", "100")])
+            .send()
+            .await?;
+        let result: SearchResult = serde_json::from_str(&ensure_success(res).await?)?;
+
+        // The search matches words, so the title is compared exactly here.
+        Ok(result
+            .items
+            .into_iter()
+            .find(|issue| issue.title == title)
+            .map(|issue| issue.number))
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for GitHubIssueSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let client = Client::new();
+        let title = config.title.render(item);
+
+        let existing = match config.issue_number {
+            Some(number) => Some(number),
+            None if config.dedupe => self.find_open_issue(&client, &title).await?,
+            None => None,
+        };
+
+        let request = match existing {
+            Some(number) => {
+                let path = format!("/repos/{}/issues/{}/comments", config.repo, number);
+                self.request(&client, Method::POST, &path)
+                    .json(&json!({ "body": input }))
+            }
+            None => {
+                let path = format!("/repos/{}/issues", config.repo);
+                self.request(&client, Method::POST, &path).json(&json!({
+                    "title": title,
+                    "body": input,
+                    "labels": config.labels,
+                    "assignees": config.assignees,
+                }))
+            }
+        };
+
+        ensure_success(request.send().await?).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_github {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_dedupe() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(
+                200,
+                r#"{"items": [{"number": 3, "title": "Release 1.0 notes"}, {"number": 7, "title": "Release 1.0"}]}"#,
+            ),
+            TestResponse::new(201, "{}"),
+            TestResponse::new(200, r#"{"items": []}"#),
+            TestResponse::new(201, "{}"),
+        ]);
+        let sink = GitHubIssueSink::new(GitHubIssueConfig {
+            api_url: server.url.clone(),
+            token: "token".to_string(),
+            repo: "owner/repo".to_string(),
+            title: "{title}".parse().unwrap(),
+            labels: vec!["feed".to_string()],
+            assignees: Vec::new(),
+            issue_number: None,
+            dedupe: true,
+        });
+        let item = TestItem::new(&[("title", "Release 1.0")]);
+
+        task::block_on(sink.sink("body".to_string(), &item)).unwrap();
+        let search = server.request();
+        assert!(search
+            .path
+            .starts_with("/search/issues?q=repo%3Aowner%2Frepo"));
+        assert_eq!(search.headers["user-agent"], "ifttt-action");
+        let comment = server.request();
+        assert_eq!(comment.path, "/repos/owner/repo/issues/7/comments");
+
+        task::block_on(sink.sink("body".to_string(), &item)).unwrap();
+        server.request();
+        let issue = server.request();
+        assert_eq!(issue.path, "/repos/owner/repo/issues");
+        let issue: Value = serde_json::from_str(&issue.body).unwrap();
+        assert_eq!(issue["title"], "Release 1.0");
+        assert_eq!(issue["labels"], json!(["feed"]));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::template::Template;
+use crate::web::ensure_success;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+// Pushes a message to a Gotify server.
+pub struct GotifySink {
+    config: GotifyConfig,
+}
+
+pub struct GotifyConfig {
+    pub server: String,
+    pub app_token: String,
+    pub title: Option<Template>,
+    pub priority: Option<i64>,
+}
+
+impl GotifySink {
+    pub fn new(config: GotifyConfig) -> Self {
+        GotifySink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for GotifySink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let mut message = json!({ "message": input });
+        if let Some(ref title) = config.title {
+            message["title"] = title.render(item).into();
+        }
+        if let Some(priority) = config.priority {
+            message["priority"] = priority.into();
+        }
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&format!("{}/message", config.server))
+            .header("X-Gotify-Key", config.app_token.as_str())
+            .json(&message)
+            .send()
+            .await?;
+
+        ensure_success(res).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_gotify {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"id": 1}"#),
+            TestResponse::new(401, r#"{"error": "Unauthorized"}"#),
+        ]);
+        let sink = GotifySink::new(GotifyConfig {
+            server: server.url.clone(),
+            app_token: "app".to_string(),
+            title: Some("{title}".parse().unwrap()),
+            priority: Some(8),
+        });
+        let item = TestItem::new(&[("title", "News")]);
+
+        task::block_on(sink.sink("hi".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/message");
+        assert_eq!(request.headers["x-gotify-key"], "app");
+        assert_eq!(request.headers["content-type"], "application/json");
+        let message: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            message,
+            json!({"message": "hi", "title": "News", "priority": 8})
+        );
+
+        assert!(task::block_on(sink.sink("hi".to_string(), &item)).is_err());
+    }
+}
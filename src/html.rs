@@ -0,0 +1,225 @@
+use crate::seen::{claim_unseen, hash_id};
+use crate::{Feed, FeedItem, State};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+
+const HTML_SEEN_ITEMS: &str = "html_seen_items";
+
+// Scrapes the nodes matching `selector` from a page which has no feed.
+pub struct HtmlFeed {
+    pub config: HtmlConfig,
+}
+
+pub struct HtmlConfig {
+    pub url: String,
+    pub selector: Selector,
+    pub fields: Vec<(String, FieldSelector)>,
+    // The field identifying an item, the whole item is used if it's absent or empty.
+    pub id: Option<String>,
+    pub count: usize,
+    pub retention: usize,
+}
+
+// A field is written as `sub selector@attribute`, both parts are optional.
+// Without a sub selector it's read from the item node itself, and without an
+// attribute it's the text of the node.
+pub struct FieldSelector {
+    selector: Option<Selector>,
+    attr: Option<String>,
+}
+
+impl FieldSelector {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let (selector, attr) = match expr.rfind('@') {
+            Some(idx) => (&expr[..idx], Some(expr[idx + 1..].trim().to_string())),
+            None => (expr, None),
+        };
+        let selector = match selector.trim() {
+            "" => None,
+            selector => Some(parse_selector(selector)?),
+        };
+
+        Ok(FieldSelector { selector, attr })
+    }
+
+    fn extract(&self, node: ElementRef, base: &Url) -> Option<String> {
+        let node = match self.selector {
+            Some(ref selector) => node.select(selector).next()?,
+            None => node,
+        };
+
+        match self.attr.as_deref() {
+            Some(attr @ "href") | Some(attr @ "src") => {
+                let link = node.value().attr(attr)?;
+                Some(
+                    base.join(link)
+                        .map_or_else(|_| link.to_string(), String::from),
+                )
+            }
+            Some(attr) => node.value().attr(attr).map(str::to_string),
+            None => Some(normalize_text(node)),
+        }
+    }
+}
+
+pub fn parse_selector(selector: &str) -> Result<Selector> {
+    Selector::parse(selector).map_err(|err| anyhow!("Invalid selector {}: {}", selector, err))
+}
+
+fn normalize_text(node: ElementRef) -> String {
+    node.text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl HtmlFeed {
+    pub fn new(config: HtmlConfig) -> Self {
+        HtmlFeed { config }
+    }
+
+    fn scrape(&self, page: &str, base: &Url) -> Vec<HtmlOutput> {
+        let document = Html::parse_document(page);
+        document
+            .select(&self.config.selector)
+            .take(self.config.count)
+            .map(|node| {
+                let fields = self
+                    .config
+                    .fields
+                    .iter()
+                    .filter_map(|(name, field)| Some((name.clone(), field.extract(node, base)?)))
+                    .collect();
+                HtmlOutput {
+                    fields,
+                    html: node.html(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Feed for HtmlFeed {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
+        let res = reqwest::get(&self.config.url).await?.error_for_status()?;
+        let base = res.url().clone();
+        let page = res.text().await?;
+
+        let outputs = self.scrape(&page, &base);
+        claim_unseen(
+            state,
+            HTML_SEEN_ITEMS,
+            self.config.retention,
+            outputs,
+            None,
+            |output| {
+                let id = match self.config.id {
+                    Some(ref id) => output[id.as_str()].to_string(),
+                    None => String::new(),
+                };
+                // Items without an id of their own are told apart by their markup.
+                if id.is_empty() {
+                    hash_id(&[&output.html])
+                } else {
+                    id
+                }
+            },
+        )
+    }
+}
+
+pub struct HtmlOutput {
+    fields: HashMap<String, String>,
+    html: String,
+}
+
+impl<'a> std::ops::Index<&'a str> for HtmlOutput {
+    type Output = str;
+    fn index(&self, field: &'a str) -> &Self::Output {
+        match field {
+            "html" => &self.html,
+            _ => self
+                .fields
+                .get(field)
+                .map(String::as_str)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_html {
+    use super::*;
+    use crate::test_server::{feed_and_commit, TestResponse, TestServer};
+
+    const PAGE: &str = r#"
+        <ul>
+            <li class="post"><a href="/posts/1">First <b>post</b></a><img src="1.png"></li>
+            <li class="post"><a href="https://other.com/2">Second</a></li>
+        </ul>
+    "#;
+
+    fn feed(url: String) -> HtmlFeed {
+        HtmlFeed::new(HtmlConfig {
+            url,
+            selector: parse_selector("li.post").unwrap(),
+            fields: vec![
+                ("title".to_string(), FieldSelector::parse("a").unwrap()),
+                ("link".to_string(), FieldSelector::parse("a@href").unwrap()),
+                (
+                    "image".to_string(),
+                    FieldSelector::parse("img@src").unwrap(),
+                ),
+            ],
+            id: Some("link".to_string()),
+            count: 10,
+            retention: 10,
+        })
+    }
+
+    #[test]
+    fn test_missing_ids() {
+        let page = r#"
+            <li class="post"><a href="/posts/1">First</a></li>
+            <li class="post">Second, without a link</li>
+            <li class="post">Third, without a link</li>
+        "#;
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, page),
+            TestResponse::new(200, page),
+        ]);
+        let feed = feed(server.url.clone());
+        let mut state = State::new();
+
+        let items = feed_and_commit(&feed, &mut state);
+        assert_eq!(items.len(), 3);
+        assert_eq!(&items[2]["title"], "");
+        assert!(feed_and_commit(&feed, &mut state).is_empty());
+    }
+
+    #[test]
+    fn test_scrape() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, PAGE),
+            TestResponse::new(200, PAGE),
+        ]);
+        let feed = feed(format!("{}/blog/", server.url));
+        let mut state = State::new();
+
+        let items = feed_and_commit(&feed, &mut state);
+        assert_eq!(items.len(), 2);
+        assert_eq!(&items[0]["title"], "First post");
+        assert_eq!(&items[0]["link"], format!("{}/posts/1", server.url));
+        assert_eq!(&items[0]["image"], format!("{}/blog/1.png", server.url));
+        assert_eq!(&items[1]["link"], "https://other.com/2");
+        assert_eq!(&items[1]["image"], "");
+        assert_eq!(server.request().path, "/blog/");
+
+        let items = feed_and_commit(&feed, &mut state);
+        assert!(items.is_empty());
+    }
+}
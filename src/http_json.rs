@@ -0,0 +1,218 @@
+use crate::seen::{hash_id, SeenItems};
+use crate::{Feed, FeedItem, State};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const HTTP_JSON_SEEN_ITEMS: &str = "http_json_seen_items";
+
+// Polls a JSON API and emits the elements selected by `items` which weren't seen before.
+pub struct HttpJsonFeed {
+    pub config: HttpJsonConfig,
+}
+
+impl HttpJsonFeed {
+    pub fn new(config: HttpJsonConfig) -> Self {
+        HttpJsonFeed { config }
+    }
+}
+
+#[async_trait]
+impl Feed for HttpJsonFeed {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
+        let config = &self.config;
+        let client = reqwest::Client::new();
+        let mut request = client
+            .request(Method::from_str(&config.method)?, &config.url)
+            .query(&config.query);
+        for (name, value) in config.headers.iter() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(ref body) = config.body {
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+        }
+
+        let document: Value = request.send().await?.error_for_status()?.json().await?;
+        let selected = jsonpath_lib::select(&document, &config.items)?;
+        // `$.items` selects the array itself, while `$.items[*]` selects its elements.
+        let items: Vec<&Value> = match selected.as_slice() {
+            [Value::Array(items)] => items.iter().collect(),
+            _ => selected,
+        };
+
+        let items: Vec<&Value> = items.into_iter().take(config.count).collect();
+        let mut news = Vec::new();
+        // Forgetting items still in the response would send them again.
+        let retention = config.retention.max(items.len());
+        let mut seen = SeenItems::load(state, HTTP_JSON_SEEN_ITEMS, retention)?;
+
+        for item in items {
+            let id = match config.id {
+                Some(ref path) => jsonpath_lib::select(item, path)?
+                    .first()
+                    .map(|id| leaf_to_string(id))
+                    .unwrap_or_default(),
+                None => String::new(),
+            };
+            // Items without an id of their own are told apart by their content.
+            let id = if id.is_empty() {
+                hash_id(&[&item.to_string()])
+            } else {
+                id
+            };
+
+            if let Some(change) = seen.claim(id) {
+                news.push(FeedItem::new(HttpJsonOutput::new(item)).with_change(change));
+            }
+        }
+
+        seen.save(state)?;
+
+        Ok(news)
+    }
+}
+
+pub struct HttpJsonConfig {
+    pub url: String,
+    pub method: String,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub body: Option<String>,
+    // JSONPath selecting the items, e.g. `$.data.releases[*]`.
+    pub items: String,
+    // JSONPath applied to each item to get its identity, e.g. `$.id`. Items
+    // without one are identified by a hash of their content.
+    pub id: Option<String>,
+    pub count: usize,
+    pub retention: usize,
+}
+
+// Every leaf of an item, addressed by its dotted path such as `author.name` or `tags.0`.
+pub struct HttpJsonOutput {
+    fields: HashMap<String, String>,
+}
+
+impl HttpJsonOutput {
+    fn new(item: &Value) -> Self {
+        let mut fields = HashMap::new();
+        flatten(item, String::new(), &mut fields);
+        HttpJsonOutput { fields }
+    }
+}
+
+fn flatten(value: &Value, path: String, fields: &mut HashMap<String, String>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten(value, child(key), fields);
+            }
+        }
+        Value::Array(values) => {
+            for (idx, value) in values.iter().enumerate() {
+                flatten(value, child(&idx.to_string()), fields);
+            }
+        }
+        leaf => {
+            fields.insert(path, leaf_to_string(leaf));
+        }
+    }
+}
+
+fn leaf_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl<'a> std::ops::Index<&'a str> for HttpJsonOutput {
+    type Output = str;
+    fn index(&self, field: &'a str) -> &Self::Output {
+        self.fields
+            .get(field)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test_http_json {
+    use super::*;
+    use crate::test_server::{feed_and_commit, TestResponse, TestServer};
+
+    fn feed(server: &TestServer, retention: usize) -> HttpJsonFeed {
+        HttpJsonFeed::new(HttpJsonConfig {
+            url: server.url.clone(),
+            method: "GET".to_string(),
+            headers: Vec::new(),
+            query: Vec::new(),
+            body: None,
+            items: "$.data[*]".to_string(),
+            id: Some("$.id".to_string()),
+            count: usize::MAX,
+            retention,
+        })
+    }
+
+    #[test]
+    fn test_missing_ids() {
+        let body = r#"{"data": [{"id": 1}, {"name": "a"}, {"name": "b"}]}"#;
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, body),
+            TestResponse::new(200, body),
+        ]);
+        let feed = feed(&server, 10);
+        let mut state = State::new();
+
+        let news = feed_and_commit(&feed, &mut state);
+        assert_eq!(news.len(), 3);
+        assert_eq!(&news[2]["name"], "b");
+        assert!(feed_and_commit(&feed, &mut state).is_empty());
+    }
+
+    #[test]
+    fn test_retention() {
+        let body = r#"{"data": [{"id": 1}, {"id": 2}, {"id": 3}]}"#;
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, body),
+            TestResponse::new(200, body),
+        ]);
+        let feed = feed(&server, 2);
+        let mut state = State::new();
+
+        assert_eq!(feed_and_commit(&feed, &mut state).len(), 3);
+        assert!(feed_and_commit(&feed, &mut state).is_empty());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let item = serde_json::json!({
+            "id": 7,
+            "author": { "name": "alice" },
+            "tags": ["a", "b"],
+            "draft": false,
+            "note": null
+        });
+
+        let output = HttpJsonOutput::new(&item);
+        assert_eq!(&output["id"], "7");
+        assert_eq!(&output["author.name"], "alice");
+        assert_eq!(&output["tags.1"], "b");
+        assert_eq!(&output["draft"], "false");
+        assert_eq!(&output["note"], "");
+    }
+}
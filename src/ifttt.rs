@@ -0,0 +1,92 @@
+use crate::template::Template;
+use crate::web::ensure_success;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+pub const IFTTT_URL: &str = "https://maker.ifttt.com";
+
+// Triggers an IFTTT Maker Webhooks event.
+pub struct IftttSink {
+    config: IftttConfig,
+}
+
+pub struct IftttConfig {
+    pub base_url: String,
+    pub event: String,
+    pub key: String,
+    // Each rendered with the item; `value1` defaults to the mapped text.
+    pub value1: Option<Template>,
+    pub value2: Option<Template>,
+    pub value3: Option<Template>,
+}
+
+impl IftttSink {
+    pub fn new(config: IftttConfig) -> Self {
+        IftttSink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for IftttSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let render = |template: &Option<Template>| template.as_ref().map(|t| t.render(item));
+        let value1 = render(&config.value1).unwrap_or(input);
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&format!(
+                "{}/trigger/{}/with/key/{}",
+                config.base_url, config.event, config.key
+            ))
+            .json(&json!({
+                "value1": value1,
+                "value2": render(&config.value2),
+                "value3": render(&config.value3),
+            }))
+            .send()
+            .await?;
+
+        ensure_success(res).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_ifttt {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_trigger() {
+        let server = TestServer::serve(vec![TestResponse::new(200, "Congratulations!")]);
+        let sink = IftttSink::new(IftttConfig {
+            base_url: server.url.clone(),
+            event: "new_post".to_string(),
+            key: "secret".to_string(),
+            value1: None,
+            value2: Some("{link}".parse().unwrap()),
+            value3: None,
+        });
+        let item = TestItem::new(&[("link", "https://example.com/1")]);
+
+        task::block_on(sink.sink("A \"quoted\" title".to_string(), &item)).unwrap();
+
+        let request = server.request();
+        assert_eq!(request.path, "/trigger/new_post/with/key/secret");
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "value1": "A \"quoted\" title",
+                "value2": "https://example.com/1",
+                "value3": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::seen::{claim_unseen, hash_id};
+use crate::{Feed, FeedItem, State};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+const JSONFEED_SEEN_ITEMS: &str = "jsonfeed_seen_items";
+
+pub struct JsonFeed {
+    pub config: JsonFeedConfig,
+}
+
+impl JsonFeed {
+    pub fn new(url: impl Into<String>, count: usize, retention: usize) -> Self {
+        let config = JsonFeedConfig {
+            url: url.into(),
+            count,
+            retention,
+        };
+        JsonFeed { config }
+    }
+}
+
+#[async_trait]
+impl Feed for JsonFeed {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
+        let document: JsonFeedDocument = reqwest::get(&self.config.url).await?.json().await?;
+
+        let outputs = document
+            .items
+            .into_iter()
+            .take(self.config.count)
+            .map(JsonFeedOutput::new)
+            .collect();
+
+        claim_unseen(
+            state,
+            JSONFEED_SEEN_ITEMS,
+            self.config.retention,
+            outputs,
+            None,
+            |output| output.id.clone(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct JsonFeedConfig {
+    pub url: String,
+    pub count: usize,
+    pub retention: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedDocument {
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedItem {
+    // A string by the spec, but version 1.0 feeds often use numbers.
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    external_url: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    content_text: Option<String>,
+    #[serde(default)]
+    content_html: Option<String>,
+    #[serde(default)]
+    date_published: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+pub struct JsonFeedOutput {
+    item: JsonFeedItem,
+    id: String,
+    tags: String,
+}
+
+impl JsonFeedOutput {
+    fn new(item: JsonFeedItem) -> Self {
+        let tags = item.tags.join(", ");
+        let id = item_id(&item);
+        JsonFeedOutput { item, id, tags }
+    }
+}
+
+// The id identifies an item, falling back to its url, then its title and date.
+fn item_id(item: &JsonFeedItem) -> String {
+    match (&item.id, item_url(item)) {
+        (Some(Value::String(id)), _) if !id.is_empty() => id.clone(),
+        (Some(Value::Number(id)), _) => id.to_string(),
+        (_, Some(url)) => url.to_string(),
+        _ => hash_id(&[
+            item.title.as_deref().unwrap_or_default(),
+            item.date_published.as_deref().unwrap_or_default(),
+        ]),
+    }
+}
+
+// Link posts only point to the page they are about.
+fn item_url(item: &JsonFeedItem) -> Option<&str> {
+    item.url.as_deref().or(item.external_url.as_deref())
+}
+
+impl<'a> std::ops::Index<&'a str> for JsonFeedOutput {
+    type Output = str;
+    fn index(&self, field: &'a str) -> &Self::Output {
+        let item = &self.item;
+        match field {
+            "id" => Some(self.id.as_str()),
+            "url" => item_url(item),
+            "title" => item.title.as_deref(),
+            "content_text" => item.content_text.as_deref(),
+            "content_html" => item.content_html.as_deref(),
+            "date_published" => item.date_published.as_deref(),
+            "tags" => Some(self.tags.as_str()),
+            // Single tags are addressed by position, e.g. `tags.0`.
+            _ => field
+                .strip_prefix("tags.")
+                .and_then(|idx| idx.parse::<usize>().ok())
+                .and_then(|idx| item.tags.get(idx))
+                .map(String::as_str),
+        }
+        .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test_jsonfeed {
+    use super::*;
+    use crate::test_server::{feed_and_commit, TestResponse, TestServer};
+    use async_std::task;
+
+    #[test]
+    fn test_feed() {
+        let first = r#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "Blog",
+            "items": [
+                {"url": "https://example.com/2", "title": "Two", "tags": ["rust", "web"]},
+                {"id": 1, "external_url": "https://example.com/1", "content_text": "One"}
+            ]
+        }"#;
+        let second = r#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "Blog",
+            "items": [
+                {"id": "3", "title": "Three", "date_published": "2020-06-01T08:00:00Z"},
+                {"url": "https://example.com/2", "title": "Two"}
+            ]
+        }"#;
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, first).header("Content-Type", "application/feed+json"),
+            TestResponse::new(200, second).header("Content-Type", "application/feed+json"),
+        ]);
+        let feed = JsonFeed::new(server.url.clone(), 10, 10);
+        let mut state = State::new();
+
+        let news = feed_and_commit(&feed, &mut state);
+        assert_eq!(news.len(), 2);
+        assert_eq!(&news[0]["id"], "https://example.com/2");
+        assert_eq!(&news[0]["tags"], "rust, web");
+        assert_eq!(&news[0]["tags.1"], "web");
+        assert_eq!(&news[1]["id"], "1");
+        assert_eq!(&news[1]["url"], "https://example.com/1");
+        assert_eq!(&news[1]["content_text"], "One");
+
+        let news = task::block_on(feed.feed(&mut state)).unwrap();
+        assert_eq!(news.len(), 1);
+        assert_eq!(&news[0]["title"], "Three");
+        assert_eq!(&news[0]["date_published"], "2020-06-01T08:00:00Z");
+    }
+}
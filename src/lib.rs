@@ -1,24 +1,79 @@
+mod atom;
+mod bark;
+mod condition;
 pub mod config;
 mod crypto;
+mod dingtalk;
+mod discord;
+mod exec;
+mod feishu;
+mod file;
+mod filter;
+mod github;
+mod gotify;
+mod html;
+mod http_json;
+mod ifttt;
+mod jsonfeed;
 mod mapper;
+mod matrix;
+mod mqtt;
+mod ntfy;
+mod page_change;
+mod pushover;
 mod rss;
+mod seen;
+mod serverchan;
+mod slack;
+mod smtp;
+mod telegram;
+mod template;
+#[cfg(test)]
+mod test_server;
 mod weather;
+mod weather_provider;
 mod web;
+mod wecom;
 
-use crate::mapper::TextMapper;
+use crate::atom::AtomFeed;
+use crate::bark::BarkSink;
+use crate::dingtalk::DingTalkSink;
+use crate::discord::DiscordSink;
+use crate::exec::ExecSink;
+use crate::feishu::FeishuSink;
+use crate::file::{FileSink, StdoutSink};
+use crate::filter::Filter;
+use crate::github::GitHubIssueSink;
+use crate::gotify::GotifySink;
+use crate::html::HtmlFeed;
+use crate::http_json::HttpJsonFeed;
+use crate::ifttt::IftttSink;
+use crate::jsonfeed::JsonFeed;
+use crate::mapper::{JsonMapper, TextMapper};
+use crate::matrix::MatrixSink;
+use crate::mqtt::MqttSink;
+use crate::ntfy::NtfySink;
+use crate::page_change::PageChangeFeed;
+use crate::pushover::PushoverSink;
 use crate::rss::RssFeed;
+use crate::seen::SeenItems;
+use crate::serverchan::ServerChanSink;
+use crate::slack::SlackSink;
+use crate::smtp::SmtpSink;
+use crate::telegram::TelegramSink;
 use crate::weather::WeatherFeed;
 use crate::web::WebSink;
+use crate::wecom::WeComSink;
 use anyhow::Result;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use cron::Schedule;
 use enum_dispatch::enum_dispatch;
 use std::collections::HashMap;
 use std::ops::Index;
 use std::str::FromStr;
 
-const ACTION_NEXT_EXEC: &'static str = "action_next_exec";
+const ACTION_NEXT_EXEC: &str = "action_next_exec";
 
 pub type States = HashMap<ActionKey, State>;
 
@@ -30,6 +85,73 @@ pub trait Indexable: for<'a> Index<&'a str, Output = str> {}
 
 impl<T> Indexable for T where T: for<'a> Index<&'a str, Output = str> {}
 
+// An item emitted by a feed, with the state changes which mark it as sent.
+//
+// The changes are only committed once the item has been sent, so an item
+// whose sink call fails is emitted again on the next run.
+pub struct FeedItem {
+    output: Box<dyn Indexable>,
+    changes: Vec<StateChange>,
+}
+
+impl FeedItem {
+    pub fn new(output: impl Indexable + 'static) -> Self {
+        FeedItem {
+            output: Box::new(output),
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn with_change(mut self, change: StateChange) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    pub fn commit(&self, state: &mut State) -> Result<()> {
+        for change in &self.changes {
+            change.apply(state)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Index<&str> for FeedItem {
+    type Output = str;
+
+    fn index(&self, key: &str) -> &str {
+        &self.output[key]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StateChange {
+    Set(String, String),
+    // Adds the id to the seen items stored under the key.
+    Seen {
+        key: &'static str,
+        id: String,
+        retention: usize,
+    },
+}
+
+impl StateChange {
+    fn apply(&self, state: &mut State) -> Result<()> {
+        match self {
+            StateChange::Set(key, value) => {
+                state.insert(key.clone(), value.clone());
+            }
+            StateChange::Seen { key, id, retention } => {
+                let mut seen = SeenItems::load(state, key, *retention)?;
+                seen.insert(id.as_str());
+                seen.save(state)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub struct ActionRun<F, M, S>
 where
     F: Feed,
@@ -90,16 +212,7 @@ where
     S: Sink,
 {
     async fn execute(&mut self) -> Result<()> {
-        if !self.should_run()? {
-            return Ok(());
-        }
-        let output = self.feed.feed(&mut self.state).await?;
-        for params in output.iter() {
-            let input = self.mapper.map(params.as_ref())?;
-            self.sink.sink(input).await?;
-        }
-
-        Ok(())
+        self.run().await
     }
 
     fn key(&self) -> ActionKey {
@@ -113,6 +226,29 @@ where
     M: Mapper,
     S: Sink,
 {
+    async fn run(&mut self) -> Result<()> {
+        if !self.should_run()? {
+            return Ok(());
+        }
+        let output = self.feed.feed(&mut self.state).await?;
+        // One failed item doesn't hold back the others. It stays uncommitted,
+        // so it's sent again on the next run, and the first error is returned.
+        let mut result = Ok(());
+        for item in output.iter() {
+            match self.send(item).await {
+                Ok(()) => item.commit(&mut self.state)?,
+                Err(err) => result = result.and(Err(err)),
+            }
+        }
+
+        result
+    }
+
+    async fn send(&self, item: &FeedItem) -> Result<()> {
+        let input = self.mapper.map(item)?;
+        self.sink.sink(input, item).await
+    }
+
     pub fn should_run(&mut self) -> Result<bool> {
         let should_run = match self.state.get(ACTION_NEXT_EXEC) {
             None => true,
@@ -148,32 +284,130 @@ pub trait Action {
 #[async_trait]
 #[enum_dispatch(Feeds)]
 pub trait Feed {
-    async fn feed(&self, state: &mut State) -> Result<Vec<Box<dyn Indexable>>>;
+    // State changes which mark an item as sent belong on the item, not in
+    // `state`, which is only for what must be recorded either way.
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>>;
 }
 
 #[enum_dispatch(Mappers)]
 pub trait Mapper {
     fn map(&self, input: &dyn Indexable) -> Result<String>;
+
+    // Applies the filter to every value the output is built from.
+    fn escape_values(&mut self, escape: Filter);
 }
 
 #[async_trait(?Send)]
 #[enum_dispatch(Sinks)]
 pub trait Sink {
-    async fn sink(&self, input: String) -> Result<()>;
+    // `input` is the mapped text, `item` the feed output it was mapped from.
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()>;
+
+    // For sinks sending markup, the filter which keeps values from breaking it.
+    fn value_escape(&self) -> Option<Filter> {
+        None
+    }
 }
 
 #[enum_dispatch]
 pub enum Feeds {
     RssFeed,
+    AtomFeed,
+    JsonFeed,
+    HttpJsonFeed,
+    HtmlFeed,
+    PageChangeFeed,
     WeatherFeed,
 }
 
 #[enum_dispatch]
 pub enum Mappers {
     TextMapper,
+    JsonMapper,
 }
 
+// Actions hold a single sink, so the size of the largest one doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[enum_dispatch]
 pub enum Sinks {
     WebSink,
+    TelegramSink,
+    SlackSink,
+    DiscordSink,
+    SmtpSink,
+    DingTalkSink,
+    WeComSink,
+    FeishuSink,
+    ServerChanSink,
+    BarkSink,
+    NtfySink,
+    GotifySink,
+    PushoverSink,
+    MatrixSink,
+    StdoutSink,
+    FileSink,
+    ExecSink,
+    GitHubIssueSink,
+    IftttSink,
+    MqttSink,
+}
+
+#[cfg(test)]
+mod test_lib {
+    use super::*;
+    use crate::test_server::TestItem;
+    use anyhow::anyhow;
+    use async_std::task;
+
+    const SENT: &str = "sent";
+
+    struct ListFeed;
+
+    #[async_trait]
+    impl Feed for ListFeed {
+        async fn feed(&self, _state: &mut State) -> Result<Vec<FeedItem>> {
+            let items = ["1", "2", "3"].iter().map(|id| {
+                let change = StateChange::Seen {
+                    key: SENT,
+                    id: id.to_string(),
+                    retention: 10,
+                };
+                FeedItem::new(TestItem::new(&[("id", id)])).with_change(change)
+            });
+            Ok(items.collect())
+        }
+    }
+
+    struct FailingSink;
+
+    #[async_trait(?Send)]
+    impl Sink for FailingSink {
+        async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+            if input == "2" {
+                return Err(anyhow!("failed to send {}", input));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_item() {
+        let mapper = TextMapper::new("{id}".parse().unwrap());
+        let mut action = ActionRun::new(
+            "action",
+            ListFeed,
+            mapper,
+            FailingSink,
+            State::new(),
+            ActionConfigs::default(),
+        );
+
+        let error = task::block_on(action.execute()).unwrap_err();
+        assert_eq!(error.to_string(), "failed to send 2");
+
+        let sent = SeenItems::load(&action.state, SENT, 10).unwrap();
+        assert!(sent.contains("1"));
+        assert!(!sent.contains("2"));
+        assert!(sent.contains("3"));
+    }
 }
@@ -1,70 +1,188 @@
+use crate::filter::Filter;
+use crate::template::{Template, TemplateError};
 use crate::{Indexable, Mapper};
 use anyhow::Result;
-use serde::export::Formatter;
-use std::collections::HashMap;
-use std::error::Error;
-use std::fmt::{self, Display};
-use std::iter::FromIterator;
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
-// use {name} to express.
+// Renders the `text` config, see `Template` for the syntax.
 pub struct TextMapper {
-    text: String,
+    template: Template,
 }
 
 impl TextMapper {
-    pub fn new(text: impl Into<String>) -> Self {
-        TextMapper { text: text.into() }
+    pub fn new(template: Template) -> Self {
+        TextMapper { template }
     }
 }
 
-#[derive(Debug)]
-enum TextMapperError {
-    NoMatchBracket,
-}
+impl Mapper for TextMapper {
+    fn map(&self, input: &dyn Indexable) -> Result<String> {
+        Ok(self.template.render(input))
+    }
 
-impl Error for TextMapperError {
-    fn description(&self) -> &str {
-        unimplemented!()
+    fn escape_values(&mut self, escape: Filter) {
+        self.template.escape_values(escape);
     }
 }
 
-impl Display for TextMapperError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+// Renders the string leaves of the `template` config, a JSON document, so the
+// output stays valid JSON whatever the fields contain.
+pub struct JsonMapper {
+    template: JsonTemplate,
+}
+
+impl JsonMapper {
+    pub fn new(template: JsonTemplate) -> Self {
+        JsonMapper { template }
     }
 }
 
-impl Mapper for TextMapper {
+impl Mapper for JsonMapper {
     fn map(&self, input: &dyn Indexable) -> Result<String> {
-        let output: Vec<_> = self.text.chars().collect();
-        let mut replacement = HashMap::new();
+        Ok(self.template.render(input).to_string())
+    }
+
+    fn escape_values(&mut self, escape: Filter) {
+        self.template.escape_values(&escape);
+    }
+}
+
+pub enum JsonTemplate {
+    String(Template),
+    Array(Vec<JsonTemplate>),
+    Object(Vec<(String, JsonTemplate)>),
+    // Numbers, booleans and null are kept as they are.
+    Value(Value),
+}
+
+#[derive(Debug)]
+pub enum JsonTemplateError {
+    Json(serde_json::Error),
+    // The dotted path of the leaf, e.g. `embeds.0.title`.
+    Template { path: String, error: TemplateError },
+}
+
+impl Display for JsonTemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonTemplateError::Json(error) => write!(f, "{}", error),
+            JsonTemplateError::Template { path, error } => write!(f, "`{}`: {}", path, error),
+        }
+    }
+}
 
-        for mut idx in 0..output.len() {
-            if output.get(idx) != Some(&'{') || (idx != 0 && output.get(idx - 1) == Some(&'\\')) {
-                continue;
+impl std::error::Error for JsonTemplateError {}
+
+impl FromStr for JsonTemplate {
+    type Err = JsonTemplateError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let value = serde_json::from_str(text).map_err(JsonTemplateError::Json)?;
+        JsonTemplate::parse(value, "")
+    }
+}
+
+impl JsonTemplate {
+    fn parse(value: Value, path: &str) -> Result<Self, JsonTemplateError> {
+        let child_path = |key: &dyn Display| {
+            if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
             }
+        };
 
-            let start = idx;
-            let mut end = None;
-            while idx < output.len() {
-                idx += 1;
-                if output.get(idx) == Some(&'}') && output.get(idx - 1) != Some(&'\\') {
-                    end = Some(idx);
-                    break;
+        let template = match value {
+            Value::String(text) => match text.parse() {
+                Ok(template) => JsonTemplate::String(template),
+                Err(error) => {
+                    let path = path.to_string();
+                    return Err(JsonTemplateError::Template { path, error });
                 }
-            }
-            let end = end.ok_or_else(|| TextMapperError::NoMatchBracket)?;
-            let expr = String::from_iter(&output[start..end + 1]);
-            let value = input.index(&expr[1..expr.len() - 1]);
+            },
+            Value::Array(values) => JsonTemplate::Array(
+                values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, value)| JsonTemplate::parse(value, &child_path(&idx)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(values) => JsonTemplate::Object(
+                values
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = JsonTemplate::parse(value, &child_path(&key))?;
+                        Ok((key, value))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            value => JsonTemplate::Value(value),
+        };
+
+        Ok(template)
+    }
 
-            replacement.insert(expr, value);
+    fn escape_values(&mut self, escape: &Filter) {
+        match self {
+            JsonTemplate::String(template) => template.escape_values(escape.clone()),
+            JsonTemplate::Array(values) => values.iter_mut().for_each(|v| v.escape_values(escape)),
+            JsonTemplate::Object(values) => values
+                .iter_mut()
+                .for_each(|(_, value)| value.escape_values(escape)),
+            JsonTemplate::Value(_) => (),
         }
+    }
 
-        let mut output = String::from_iter(output);
-        for (from, to) in replacement {
-            output = output.replace(&from, &to[..]);
+    fn render(&self, input: &dyn Indexable) -> Value {
+        match self {
+            JsonTemplate::String(template) => Value::String(template.render(input)),
+            JsonTemplate::Array(values) => {
+                Value::Array(values.iter().map(|value| value.render(input)).collect())
+            }
+            JsonTemplate::Object(values) => Value::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.render(input)))
+                    .collect(),
+            ),
+            JsonTemplate::Value(value) => value.clone(),
         }
+    }
+}
+
+#[cfg(test)]
+mod test_mapper {
+    use super::*;
+    use crate::test_server::TestItem;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_mapper() {
+        let template = r#"{"content": "{title}", "embeds": [{"url": "{link}", "color": 5}]}"#;
+        let mapper = JsonMapper::new(template.parse().unwrap());
+        let item = TestItem::new(&[("title", "Say \"hi\"\n"), ("link", "https://a.b")]);
+
+        let output: Value = serde_json::from_str(&mapper.map(&item).unwrap()).unwrap();
+        assert_eq!(
+            output,
+            json!({
+                "content": "Say \"hi\"\n",
+                "embeds": [{"url": "https://a.b", "color": 5}],
+            })
+        );
+
+        // Keys keep the order of the template.
+        let mapper = JsonMapper::new(r#"{"text": "{link}", "at": 1}"#.parse().unwrap());
+        assert_eq!(
+            mapper.map(&item).unwrap(),
+            r#"{"text":"https://a.b","at":1}"#
+        );
 
-        Ok(output)
+        let error = r#"{"embeds": [{"title": "{title"}]}"#.parse::<JsonTemplate>();
+        assert_eq!(
+            error.err().unwrap().to_string(),
+            "`embeds.0.title`: Expected `}` at line 1, column 7."
+        );
     }
 }
@@ -0,0 +1,118 @@
+use crate::config::ConfigError;
+use crate::web::{ensure_success, send_with_retry};
+use crate::{Indexable, Sink};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Url;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Sends a message to a Matrix room.
+pub struct MatrixSink {
+    config: MatrixConfig,
+    // Makes transaction ids unique within a run.
+    sent: AtomicUsize,
+}
+
+pub struct MatrixConfig {
+    pub homeserver: String,
+    pub room_id: String,
+    pub access_token: String,
+    pub msgtype: MsgType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MsgType {
+    Text,
+    // Like text, but bots shouldn't react to it.
+    Notice,
+}
+
+impl FromStr for MsgType {
+    type Err = ConfigError;
+    fn from_str(msgtype: &str) -> Result<Self, Self::Err> {
+        match msgtype {
+            "m.text" => Ok(MsgType::Text),
+            "m.notice" => Ok(MsgType::Notice),
+            _ => Err(ConfigError::UnsupportedValue(msgtype.to_string())),
+        }
+    }
+}
+
+impl MatrixSink {
+    pub fn new(config: MatrixConfig) -> Self {
+        MatrixSink {
+            config,
+            sent: AtomicUsize::new(0),
+        }
+    }
+
+    fn send_url(&self) -> Result<Url> {
+        let txn_id = format!(
+            "ifttt-{}-{}",
+            Utc::now().timestamp_millis(),
+            self.sent.fetch_add(1, Ordering::SeqCst)
+        );
+        let mut url = Url::parse(&self.config.homeserver)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Invalid homeserver {}.", self.config.homeserver))?
+            .pop_if_empty()
+            .extend(&["_matrix", "client", "v3", "rooms"])
+            .push(&self.config.room_id)
+            .extend(&["send", "m.room.message", &txn_id]);
+
+        Ok(url)
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for MatrixSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let msgtype = match self.config.msgtype {
+            MsgType::Text => "m.text",
+            MsgType::Notice => "m.notice",
+        };
+        let content = json!({ "msgtype": msgtype, "body": input });
+
+        let client = reqwest::Client::new();
+        let request = client
+            .put(self.send_url()?)
+            .bearer_auth(&self.config.access_token)
+            .json(&content);
+
+        ensure_success(send_with_retry(request).await?).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_matrix {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![TestResponse::new(200, r#"{"event_id": "$1"}"#)]);
+        let sink = MatrixSink::new(MatrixConfig {
+            homeserver: format!("{}/", server.url),
+            room_id: "!room:example.org".to_string(),
+            access_token: "token".to_string(),
+            msgtype: MsgType::Notice,
+        });
+
+        task::block_on(sink.sink("hello".to_string(), &TestItem::new(&[]))).unwrap();
+
+        let request = server.request();
+        assert_eq!(request.method, "PUT");
+        assert!(request
+            .path
+            .starts_with("/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/ifttt-"));
+        assert_eq!(request.headers["authorization"], "Bearer token");
+        let content: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(content, json!({"msgtype": "m.notice", "body": "hello"}));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::config::ConfigError;
+use crate::template::Template;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_std::task;
+use async_trait::async_trait;
+use rumqttc::{Client, Event, MqttOptions, Outgoing, Packet, QoS, RecvTimeoutError, Transport};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PORT: u16 = 1883;
+pub const DEFAULT_TLS_PORT: u16 = 8883;
+pub const DEFAULT_TIMEOUT: u64 = 30;
+pub const DEFAULT_QOS: MqttQoS = MqttQoS(QoS::AtMostOnce);
+
+// A broker drops a connection when another one takes its client id, so each
+// action gets its own.
+pub fn default_client_id(action: &str) -> String {
+    format!("ifttt-action-{}", action)
+}
+
+// Publishes the mapped text to an MQTT broker, one connection per item.
+pub struct MqttSink {
+    config: MqttConfig,
+}
+
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub credentials: Option<(String, String)>,
+    pub tls: bool,
+    pub topic: Template,
+    pub qos: MqttQoS,
+    pub retain: bool,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MqttQoS(QoS);
+
+impl FromStr for MqttQoS {
+    type Err = ConfigError;
+    fn from_str(qos: &str) -> Result<Self, Self::Err> {
+        match qos {
+            "0" => Ok(MqttQoS(QoS::AtMostOnce)),
+            "1" => Ok(MqttQoS(QoS::AtLeastOnce)),
+            "2" => Ok(MqttQoS(QoS::ExactlyOnce)),
+            _ => Err(ConfigError::UnsupportedValue(qos.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MqttError {
+    Timeout(Duration),
+    Disconnected,
+}
+
+impl Display for MqttError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MqttError::Timeout(timeout) => write!(f, "No ack from the broker in {:?}.", timeout),
+            MqttError::Disconnected => write!(f, "The broker closed the connection."),
+        }
+    }
+}
+
+impl std::error::Error for MqttError {}
+
+impl MqttSink {
+    pub fn new(config: MqttConfig) -> Self {
+        MqttSink { config }
+    }
+
+    fn options(&self) -> MqttOptions {
+        let config = &self.config;
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        if let Some((ref username, ref password)) = config.credentials {
+            options.set_credentials(username, password);
+        }
+        if config.tls {
+            // rumqttc leaves the choice of a crypto provider to us. Installing
+            // fails once one is installed, which is as good.
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            options.set_transport(Transport::tls_with_default_config());
+        }
+        options
+    }
+}
+
+fn publish(
+    options: MqttOptions,
+    topic: String,
+    payload: String,
+    qos: QoS,
+    retain: bool,
+    timeout: Duration,
+) -> Result<()> {
+    let (client, mut connection) = Client::new(options, 10);
+    client.publish(topic, qos, retain, payload)?;
+
+    let started = Instant::now();
+    let mut disconnecting = false;
+    loop {
+        let remaining = timeout
+            .checked_sub(started.elapsed())
+            .ok_or(MqttError::Timeout(timeout))?;
+        let event = match connection.recv_timeout(remaining) {
+            Ok(event) => event?,
+            Err(RecvTimeoutError::Timeout) => return Err(MqttError::Timeout(timeout).into()),
+            Err(RecvTimeoutError::Disconnected) => return Err(MqttError::Disconnected.into()),
+        };
+
+        // Disconnect only once the broker has the message at the requested QoS.
+        let delivered = match event {
+            Event::Outgoing(Outgoing::Publish(_)) => qos == QoS::AtMostOnce,
+            Event::Incoming(Packet::PubAck(_)) => qos == QoS::AtLeastOnce,
+            Event::Incoming(Packet::PubComp(_)) => true,
+            Event::Outgoing(Outgoing::Disconnect) => return Ok(()),
+            _ => false,
+        };
+        if delivered && !disconnecting {
+            client.disconnect()?;
+            disconnecting = true;
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for MqttSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let topic = config.topic.render(item);
+        let options = self.options();
+        let MqttQoS(qos) = config.qos;
+        let retain = config.retain;
+        let timeout = config.timeout;
+
+        // The client runs its own runtime, so it's kept off the async executor.
+        task::spawn_blocking(move || publish(options, topic, input, qos, retain, timeout)).await
+    }
+}
+
+#[cfg(test)]
+mod test_mqtt {
+    use super::*;
+    use crate::test_server::TestItem;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    struct Publish {
+        flags: u8,
+        topic: String,
+        payload: String,
+    }
+
+    fn read_packet(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        let header = byte[0];
+        let (mut len, mut shift) = (0usize, 0);
+        loop {
+            stream.read_exact(&mut byte).unwrap();
+            len |= ((byte[0] & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).unwrap();
+        (header, body)
+    }
+
+    // Accepts one connection, acks the CONNECT and a QoS 1 PUBLISH, then waits for DISCONNECT.
+    fn serve_broker() -> (u16, thread::JoinHandle<Publish>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (header, _) = read_packet(&mut stream);
+            assert_eq!(header >> 4, 1);
+            stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap();
+
+            let (header, body) = read_packet(&mut stream);
+            assert_eq!(header >> 4, 3);
+            let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+            let topic = String::from_utf8(body[2..2 + topic_len].to_vec()).unwrap();
+            let packet_id = &body[2 + topic_len..4 + topic_len];
+            let payload = String::from_utf8(body[4 + topic_len..].to_vec()).unwrap();
+            stream
+                .write_all(&[0x40, 0x02, packet_id[0], packet_id[1]])
+                .unwrap();
+
+            let (disconnect, _) = read_packet(&mut stream);
+            assert_eq!(disconnect >> 4, 14);
+
+            Publish {
+                flags: header & 0x0f,
+                topic,
+                payload,
+            }
+        });
+        (port, handle)
+    }
+
+    fn sink(port: u16, tls: bool) -> MqttSink {
+        MqttSink::new(MqttConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            client_id: default_client_id("news"),
+            credentials: None,
+            tls,
+            topic: "home/{room}".parse().unwrap(),
+            qos: "1".parse().unwrap(),
+            retain: true,
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn test_tls_provider() {
+        sink(DEFAULT_TLS_PORT, true).options();
+        assert!(rustls::crypto::CryptoProvider::get_default().is_some());
+    }
+
+    #[test]
+    fn test_publish() {
+        let (port, broker) = serve_broker();
+        let sink = sink(port, false);
+        let item = TestItem::new(&[("room", "kitchen")]);
+
+        task::block_on(sink.sink("Lights on".to_string(), &item)).unwrap();
+
+        let publish = broker.join().unwrap();
+        assert_eq!(publish.topic, "home/kitchen");
+        assert_eq!(publish.payload, "Lights on");
+        // QoS 1 and the retain bit.
+        assert_eq!(publish.flags, 0b0011);
+    }
+}
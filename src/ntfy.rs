@@ -0,0 +1,131 @@
+use crate::config::ConfigError;
+use crate::template::Template;
+use crate::web::ensure_success;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+use std::str::FromStr;
+
+pub const NTFY_SERVER: &str = "https://ntfy.sh";
+
+// Publishes to an ntfy topic.
+pub struct NtfySink {
+    config: NtfyConfig,
+}
+
+pub struct NtfyConfig {
+    pub server: String,
+    pub topic: String,
+    // Needed for topics with access control.
+    pub token: Option<String>,
+    pub title: Option<Template>,
+    pub click: Option<Template>,
+    pub priority: Option<NtfyPriority>,
+    pub tags: Vec<String>,
+}
+
+// From 1 (min) to 5 (max), also accepted by name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NtfyPriority(u8);
+
+impl FromStr for NtfyPriority {
+    type Err = ConfigError;
+    fn from_str(priority: &str) -> Result<Self, Self::Err> {
+        match priority {
+            "1" | "min" => Ok(NtfyPriority(1)),
+            "2" | "low" => Ok(NtfyPriority(2)),
+            "3" | "default" => Ok(NtfyPriority(3)),
+            "4" | "high" => Ok(NtfyPriority(4)),
+            "5" | "max" => Ok(NtfyPriority(5)),
+            _ => Err(ConfigError::UnsupportedValue(priority.to_string())),
+        }
+    }
+}
+
+impl NtfySink {
+    pub fn new(config: NtfyConfig) -> Self {
+        NtfySink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for NtfySink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        // Publishing as JSON keeps non ASCII titles intact, unlike headers.
+        let mut message = json!({
+            "topic": config.topic,
+            "message": input,
+            "tags": config.tags,
+        });
+        if let Some(ref title) = config.title {
+            message["title"] = title.render(item).into();
+        }
+        if let Some(ref click) = config.click {
+            message["click"] = click.render(item).into();
+        }
+        if let Some(NtfyPriority(priority)) = config.priority {
+            message["priority"] = priority.into();
+        }
+
+        let client = reqwest::Client::new();
+        let mut request = client.post(&config.server).json(&message);
+        if let Some(ref token) = config.token {
+            request = request.bearer_auth(token);
+        }
+
+        ensure_success(request.send().await?).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_ntfy {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_priority() {
+        assert_eq!("high".parse::<NtfyPriority>().unwrap(), NtfyPriority(4));
+        assert_eq!("1".parse::<NtfyPriority>().unwrap(), NtfyPriority(1));
+        assert!("6".parse::<NtfyPriority>().is_err());
+    }
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![TestResponse::new(200, r#"{"id": "abc"}"#)]);
+        let sink = NtfySink::new(NtfyConfig {
+            server: server.url.clone(),
+            topic: "news".to_string(),
+            token: Some("tk_secret".to_string()),
+            title: Some("{title}".parse().unwrap()),
+            click: Some("{link}".parse().unwrap()),
+            priority: Some(NtfyPriority(4)),
+            tags: vec!["rss".to_string()],
+        });
+        let item = TestItem::new(&[("title", "Nouvelles"), ("link", "https://example.com/1")]);
+
+        task::block_on(sink.sink("hi".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/");
+        assert_eq!(request.headers["authorization"], "Bearer tk_secret");
+        assert_eq!(request.headers["content-type"], "application/json");
+        let message: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            message,
+            json!({
+                "topic": "news",
+                "message": "hi",
+                "tags": ["rss"],
+                "title": "Nouvelles",
+                "click": "https://example.com/1",
+                "priority": 4
+            })
+        );
+    }
+}
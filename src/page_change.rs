@@ -0,0 +1,172 @@
+use crate::html::parse_selector;
+use crate::seen::hash_id;
+use crate::{Feed, FeedItem, State, StateChange};
+use anyhow::Result;
+use async_trait::async_trait;
+use scraper::{ElementRef, Html, Selector};
+use similar::TextDiff;
+
+const PAGE_CHANGE_HASH: &str = "page_change_hash";
+const PAGE_CHANGE_TEXT: &str = "page_change_text";
+
+// Emits a single item whenever the text of a page, or of the part matching
+// `selector`, differs from the previous run.
+pub struct PageChangeFeed {
+    pub config: PageChangeConfig,
+}
+
+pub struct PageChangeConfig {
+    pub url: String,
+    pub selector: Option<Selector>,
+}
+
+impl PageChangeFeed {
+    pub fn new(url: impl Into<String>, selector: Option<&str>) -> Result<Self> {
+        let config = PageChangeConfig {
+            url: url.into(),
+            selector: selector.map(parse_selector).transpose()?,
+        };
+        Ok(PageChangeFeed { config })
+    }
+
+    // One line per text node, so markup and indentation changes don't count.
+    fn normalize(&self, page: &str) -> String {
+        let document = Html::parse_document(page);
+        let nodes: Vec<_> = match self.config.selector {
+            Some(ref selector) => document.select(selector).collect(),
+            None => vec![document.root_element()],
+        };
+
+        nodes
+            .into_iter()
+            .flat_map(visible_text)
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .map(|line| line + "\n")
+            .collect()
+    }
+}
+
+// Scripts and styles aren't shown, and often change on every load.
+fn visible_text(node: ElementRef<'_>) -> impl Iterator<Item = &str> {
+    node.descendants().filter_map(|node| {
+        let hidden = node.ancestors().any(|parent| {
+            parent
+                .value()
+                .as_element()
+                .is_some_and(|element| matches!(element.name(), "script" | "style"))
+        });
+        match node.value().as_text() {
+            Some(text) if !hidden => Some(&**text),
+            _ => None,
+        }
+    })
+}
+
+#[async_trait]
+impl Feed for PageChangeFeed {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
+        let page = reqwest::get(&self.config.url)
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let text = self.normalize(&page);
+        let new_hash = hash_id(&[&text]);
+
+        let old_hash = match state.get(PAGE_CHANGE_HASH) {
+            Some(old_hash) if *old_hash == new_hash => return Ok(Vec::new()),
+            Some(old_hash) => old_hash.clone(),
+            // The first run only records what the page looks like.
+            None => {
+                state.insert(PAGE_CHANGE_HASH.to_string(), new_hash);
+                state.insert(PAGE_CHANGE_TEXT.to_string(), text);
+                return Ok(Vec::new());
+            }
+        };
+        let old_text = state.get(PAGE_CHANGE_TEXT).cloned().unwrap_or_default();
+
+        let diff = TextDiff::from_lines(&old_text, &text)
+            .unified_diff()
+            .header("old", "new")
+            .to_string();
+
+        let hash_change = StateChange::Set(PAGE_CHANGE_HASH.to_string(), new_hash.clone());
+        let text_change = StateChange::Set(PAGE_CHANGE_TEXT.to_string(), text.clone());
+        let output = PageChangeOutput {
+            url: self.config.url.clone(),
+            old_hash,
+            new_hash,
+            diff,
+            text,
+        };
+
+        Ok(vec![FeedItem::new(output)
+            .with_change(hash_change)
+            .with_change(text_change)])
+    }
+}
+
+pub struct PageChangeOutput {
+    url: String,
+    old_hash: String,
+    new_hash: String,
+    diff: String,
+    text: String,
+}
+
+impl<'a> std::ops::Index<&'a str> for PageChangeOutput {
+    type Output = str;
+    fn index(&self, field: &'a str) -> &Self::Output {
+        match field {
+            "url" => &self.url,
+            "old_hash" => &self.old_hash,
+            "new_hash" => &self.new_hash,
+            "diff" => &self.diff,
+            "text" => &self.text,
+            _ => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_page_change {
+    use super::*;
+    use crate::test_server::{feed_and_commit, TestResponse, TestServer};
+
+    #[test]
+    fn test_change() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(
+                200,
+                "<div id='price'><p>Price</p><p>10</p></div><p>ad 1</p>",
+            ),
+            TestResponse::new(
+                200,
+                "<div id='price'><p>Price</p><p>10</p></div><p>ad 2</p>",
+            ),
+            TestResponse::new(200, "<div id='price'><p>Price</p><p>12</p></div>"),
+        ]);
+        let feed = PageChangeFeed::new(server.url.clone(), Some("#price")).unwrap();
+        let mut state = State::new();
+
+        assert!(feed_and_commit(&feed, &mut state).is_empty());
+        assert!(feed_and_commit(&feed, &mut state).is_empty());
+
+        let items = feed_and_commit(&feed, &mut state);
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            &items[0]["diff"],
+            "--- old\n+++ new\n@@ -1,2 +1,2 @@\n Price\n-10\n+12\n"
+        );
+        assert_eq!(&items[0]["new_hash"], state[PAGE_CHANGE_HASH].as_str());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let feed = PageChangeFeed::new("", None).unwrap();
+        let page = "<html><head><title>Shop</title><style>p { color: red; }</style></head>\
+                    <body><p>Price:  <b>10</b></p><script>var token = 'a1';</script></body></html>";
+        assert_eq!(feed.normalize(page), "Shop\nPrice:\n10\n");
+    }
+}
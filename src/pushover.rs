@@ -0,0 +1,111 @@
+use crate::template::Template;
+use crate::web::check_api_code;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub const PUSHOVER_API_URL: &str = "https://api.pushover.net";
+
+// Sends a Pushover notification.
+pub struct PushoverSink {
+    config: PushoverConfig,
+}
+
+pub struct PushoverConfig {
+    pub api_url: String,
+    // The application's API token.
+    pub app_key: String,
+    pub user_key: String,
+    pub title: Option<Template>,
+    pub url: Option<Template>,
+    pub sound: Option<String>,
+    pub priority: Option<i8>,
+}
+
+impl PushoverSink {
+    pub fn new(config: PushoverConfig) -> Self {
+        PushoverSink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for PushoverSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let mut form = vec![
+            ("token", config.app_key.clone()),
+            ("user", config.user_key.clone()),
+            ("message", input),
+        ];
+        if let Some(ref title) = config.title {
+            form.push(("title", title.render(item)));
+        }
+        if let Some(ref url) = config.url {
+            form.push(("url", url.render(item)));
+        }
+        if let Some(ref sound) = config.sound {
+            form.push(("sound", sound.clone()));
+        }
+        if let Some(priority) = config.priority {
+            form.push(("priority", priority.to_string()));
+        }
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&format!("{}/1/messages.json", config.api_url))
+            .form(&form)
+            .send()
+            .await?;
+
+        // Invalid requests get a 4xx status, with the reasons in the body.
+        let body = res.text().await?;
+        check_api_code("Pushover", &body, "status", "1", "errors")
+    }
+}
+
+#[cfg(test)]
+mod test_pushover {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use crate::web::ApiError;
+    use async_std::task;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"status":1,"request":"647d2300"}"#),
+            TestResponse::new(
+                400,
+                r#"{"user":"invalid","errors":["user key is invalid","sound is invalid"],"status":0,"request":"5042853c"}"#,
+            ),
+        ]);
+        let sink = PushoverSink::new(PushoverConfig {
+            api_url: server.url.clone(),
+            app_key: "app".to_string(),
+            user_key: "user".to_string(),
+            title: Some("{title}".parse().unwrap()),
+            url: None,
+            sound: Some("magic".to_string()),
+            priority: Some(1),
+        });
+        let item = TestItem::new(&[("title", "News")]);
+
+        task::block_on(sink.sink("hi there".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/1/messages.json");
+        assert_eq!(
+            request.headers["content-type"],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(
+            request.body,
+            "token=app&user=user&message=hi+there&title=News&sound=magic&priority=1"
+        );
+
+        let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+        let err = err.downcast::<ApiError>().unwrap();
+        assert_eq!(err.code, "0");
+        assert_eq!(err.message, "user key is invalid; sound is invalid");
+    }
+}
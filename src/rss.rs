@@ -1,20 +1,25 @@
-use crate::{Feed, Indexable, State};
+use crate::seen::{claim_unseen, hash_id};
+use crate::{Feed, FeedItem, State};
 use anyhow::Result;
 use async_trait::async_trait;
 use rss::{Channel, Item};
 use serde::Deserialize;
+use std::collections::HashMap;
 
+// Only read to migrate states written before `rss_seen_items` existed.
 const RSS_LAST_LINK: &str = "rss_last_link";
+const RSS_SEEN_ITEMS: &str = "rss_seen_items";
 
 pub struct RssFeed {
     pub config: RssConfig,
 }
 
 impl RssFeed {
-    pub fn new(url: impl Into<String>, count: usize) -> Self {
+    pub fn new(url: impl Into<String>, count: usize, retention: usize) -> Self {
         let config = RssConfig {
             url: url.into(),
             count,
+            retention,
         };
         RssFeed { config }
     }
@@ -22,52 +27,263 @@ impl RssFeed {
 
 #[async_trait]
 impl Feed for RssFeed {
-    async fn feed(&self, state: &mut State) -> Result<Vec<Box<dyn Indexable>>> {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
         let res = reqwest::get(&self.config.url).await?;
         let content = res.bytes().await?;
         let channel = Channel::read_from(&content[..])?;
 
-        let mut news: Vec<Box<dyn Indexable>> = Vec::new();
-        let last_news_link = state.get(RSS_LAST_LINK).map(String::as_str);
-        let mut latest_link: Option<String> = None;
+        let outputs: Vec<_> = channel
+            .items()
+            .iter()
+            .take(self.config.count)
+            .map(|item| RssOutput::new(item, &channel))
+            .collect();
 
-        for item in channel.into_items().into_iter().take(self.config.count) {
-            if item.link() == last_news_link {
-                break;
+        // Everything from the legacy marker on has been sent already.
+        let last_news_link = state
+            .remove(RSS_LAST_LINK)
+            .filter(|_| !state.contains_key(RSS_SEEN_ITEMS));
+        let sent_from = last_news_link
+            .and_then(|link| outputs.iter().position(|output| output["link"] == link));
+
+        claim_unseen(
+            state,
+            RSS_SEEN_ITEMS,
+            self.config.retention,
+            outputs,
+            sent_from,
+            item_id,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RssConfig {
+    pub url: String,
+    pub count: usize,
+    pub retention: usize,
+}
+
+// The guid identifies an item, falling back to its link, then its title and date.
+fn item_id(output: &RssOutput) -> String {
+    match (output.fields.get("guid"), output.fields.get("link")) {
+        (Some(guid), _) => guid.clone(),
+        (None, Some(link)) => link.clone(),
+        (None, None) => hash_id(&[&output["title"], &output["pubDate"]]),
+    }
+}
+
+// Every field of an item, flattened into dotted names such as `enclosure.url`.
+pub struct RssOutput {
+    fields: HashMap<String, String>,
+}
+
+impl RssOutput {
+    fn new(item: &Item, channel: &Channel) -> Self {
+        let mut fields = HashMap::new();
+        let mut insert = |name: &str, value: Option<&str>| {
+            if let Some(value) = value {
+                fields.insert(name.to_string(), value.to_string());
             }
+        };
 
-            if latest_link.is_none() {
-                latest_link = item.link().map(str::to_string);
+        insert("title", item.title());
+        insert("link", item.link());
+        insert("description", item.description());
+        insert("content", item.content());
+        insert("author", item.author());
+        insert("comments", item.comments());
+        insert("pubDate", item.pub_date());
+        insert("guid", item.guid().map(|guid| guid.value()));
+        if let Some(enclosure) = item.enclosure() {
+            insert("enclosure.url", Some(enclosure.url()));
+            insert("enclosure.type", Some(enclosure.mime_type()));
+            insert("enclosure.length", Some(enclosure.length()));
+        }
+        if let Some(source) = item.source() {
+            insert("source", source.title().or_else(|| Some(source.url())));
+            insert("source.url", Some(source.url()));
+        }
+        insert("channel.title", Some(channel.title()));
+        insert("channel.link", Some(channel.link()));
+        insert("channel.description", Some(channel.description()));
+
+        let categories: Vec<_> = item.categories().iter().map(|c| c.name()).collect();
+        insert_list(&mut fields, "categories", &categories);
+
+        if let Some(dc) = item.dublin_core_ext() {
+            let lists: [(&str, &[String]); 15] = [
+                ("dc.contributor", dc.contributors()),
+                ("dc.coverage", dc.coverages()),
+                ("dc.creator", dc.creators()),
+                ("dc.date", dc.dates()),
+                ("dc.description", dc.descriptions()),
+                ("dc.format", dc.formats()),
+                ("dc.identifier", dc.identifiers()),
+                ("dc.language", dc.languages()),
+                ("dc.publisher", dc.publishers()),
+                ("dc.relation", dc.relations()),
+                ("dc.rights", dc.rights()),
+                ("dc.source", dc.sources()),
+                ("dc.subject", dc.subjects()),
+                ("dc.title", dc.titles()),
+                ("dc.type", dc.types()),
+            ];
+            for (name, values) in lists.iter() {
+                let values: Vec<_> = values.iter().map(String::as_str).collect();
+                insert_list(&mut fields, name, &values);
             }
+        }
 
-            news.push(Box::new(RssOutput(item)));
+        if let Some(itunes) = item.itunes_ext() {
+            let values = [
+                ("itunes.author", itunes.author()),
+                ("itunes.block", itunes.block()),
+                ("itunes.image", itunes.image()),
+                ("itunes.duration", itunes.duration()),
+                ("itunes.explicit", itunes.explicit()),
+                ("itunes.closed_captioned", itunes.closed_captioned()),
+                ("itunes.order", itunes.order()),
+                ("itunes.subtitle", itunes.subtitle()),
+                ("itunes.summary", itunes.summary()),
+                ("itunes.keywords", itunes.keywords()),
+                ("itunes.episode", itunes.episode()),
+                ("itunes.season", itunes.season()),
+                ("itunes.episode_type", itunes.episode_type()),
+            ];
+            for (name, value) in values.iter() {
+                if let Some(value) = value {
+                    fields.insert(name.to_string(), value.to_string());
+                }
+            }
         }
 
-        if let Some(link) = latest_link {
-            state.insert(RSS_LAST_LINK.to_string(), link);
+        // Any other namespace, e.g. `media.content.url` for `<media:content url="..."/>`.
+        for (prefix, extensions) in item.extensions() {
+            for (name, extensions) in extensions {
+                let key = format!("{}.{}", prefix, name);
+                if let Some(extension) = extensions.first() {
+                    for (attr, value) in extension.attrs() {
+                        fields.insert(format!("{}.{}", key, attr), value.clone());
+                    }
+                }
+                let values: Vec<_> = extensions.iter().filter_map(|e| e.value()).collect();
+                insert_list(&mut fields, &key, &values);
+            }
         }
 
-        Ok(news)
+        RssOutput { fields }
     }
 }
 
-#[derive(Deserialize)]
-pub struct RssConfig {
-    pub url: String,
-    pub count: usize,
+// Lists are exposed joined under `name` and one by one under `name.0`, `name.1`...
+fn insert_list(fields: &mut HashMap<String, String>, name: &str, values: &[&str]) {
+    if values.is_empty() {
+        return;
+    }
+    fields.insert(name.to_string(), values.join(", "));
+    for (idx, value) in values.iter().enumerate() {
+        fields.insert(format!("{}.{}", name, idx), value.to_string());
+    }
 }
 
-pub struct RssOutput(Item);
-
 impl<'a> std::ops::Index<&'a str> for RssOutput {
     type Output = str;
     fn index(&self, field: &'a str) -> &Self::Output {
-        match field {
-            "description" => self.0.description(),
-            "title" => self.0.title(),
-            "link" => self.0.link(),
-            _ => None,
-        }
-        .unwrap_or_default()
+        self.fields
+            .get(field)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test_rss {
+    use super::*;
+    use crate::test_server::{feed_and_commit, TestResponse, TestServer};
+
+    fn rss(items: &[(&str, &str)]) -> String {
+        let items: String = items
+            .iter()
+            .map(|(guid, title)| {
+                format!(
+                    "<item><guid>{guid}</guid><title>{title}</title>\
+                     <link>https://example.com/{guid}</link></item>",
+                    guid = guid,
+                    title = title
+                )
+            })
+            .collect();
+        format!(
+            "<rss version=\"2.0\"><channel><title>Blog</title>\
+             <link>https://example.com</link><description>A blog</description>\
+             {}</channel></rss>",
+            items
+        )
+    }
+
+    #[test]
+    fn test_feed() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, rss(&[("3", "Three"), ("2", "Two"), ("1", "One")])),
+            // Reordered, edited and removed items aren't sent again.
+            TestResponse::new(200, rss(&[("1", "One"), ("4", "Four"), ("3", "Three!")])),
+        ]);
+        // Keeps every item in the window, though only one is asked for.
+        let feed = RssFeed::new(server.url.clone(), 3, 1);
+        let mut state = State::new();
+        // Written by versions which only kept the last link sent.
+        state.insert(
+            RSS_LAST_LINK.to_string(),
+            "https://example.com/2".to_string(),
+        );
+
+        let news = feed_and_commit(&feed, &mut state);
+        assert_eq!(news.len(), 1);
+        assert_eq!(&news[0]["title"], "Three");
+        assert!(!state.contains_key(RSS_LAST_LINK));
+        assert_eq!(state[RSS_SEEN_ITEMS], r#"["3","2","1"]"#);
+
+        let news = feed_and_commit(&feed, &mut state);
+        assert_eq!(news.len(), 1);
+        assert_eq!(&news[0]["title"], "Four");
+        assert_eq!(&news[0]["link"], "https://example.com/4");
+        assert_eq!(state[RSS_SEEN_ITEMS], r#"["4","1","3"]"#);
+    }
+
+    #[test]
+    fn test_item_fields() {
+        let xml = r#"
+        <rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"
+             xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+            <channel>
+                <title>Podcast</title>
+                <link>https://example.com</link>
+                <description>A podcast</description>
+                <item>
+                    <title>Episode 1</title>
+                    <pubDate>Mon, 01 Jun 2020 08:00:00 GMT</pubDate>
+                    <category>Tech</category>
+                    <category>Rust</category>
+                    <enclosure url="https://example.com/1.mp3" type="audio/mpeg" length="42"/>
+                    <dc:creator>Alice</dc:creator>
+                    <itunes:duration>01:02:03</itunes:duration>
+                </item>
+            </channel>
+        </rss>
+        "#;
+
+        let channel = Channel::read_from(xml.as_bytes()).unwrap();
+        let output = RssOutput::new(&channel.items()[0], &channel);
+
+        assert_eq!(&output["channel.title"], "Podcast");
+        assert_eq!(&output["pubDate"], "Mon, 01 Jun 2020 08:00:00 GMT");
+        assert_eq!(&output["categories"], "Tech, Rust");
+        assert_eq!(&output["categories.1"], "Rust");
+        assert_eq!(&output["enclosure.url"], "https://example.com/1.mp3");
+        assert_eq!(&output["enclosure.type"], "audio/mpeg");
+        assert_eq!(&output["dc.creator"], "Alice");
+        assert_eq!(&output["itunes.duration"], "01:02:03");
+        assert_eq!(&output["comments"], "");
     }
 }
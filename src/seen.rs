@@ -0,0 +1,173 @@
+use crate::{FeedItem, Indexable, State, StateChange};
+use anyhow::Result;
+use ring::digest::{digest, SHA256};
+use std::collections::VecDeque;
+
+pub(crate) const DEFAULT_RETENTION: usize = 200;
+
+// The ids of items a feed has already emitted, newest first.
+//
+// It's persisted as a JSON array in the action state, and only the latest
+// `retention` ids are kept so the state doesn't grow without bound.
+pub(crate) struct SeenItems {
+    key: &'static str,
+    retention: usize,
+    ids: VecDeque<String>,
+    fresh: Vec<String>,
+    claimed: Vec<String>,
+}
+
+impl SeenItems {
+    pub fn load(state: &State, key: &'static str, retention: usize) -> Result<Self> {
+        let ids = match state.get(key) {
+            Some(ids) => serde_json::from_str(ids)?,
+            None => VecDeque::new(),
+        };
+
+        Ok(SeenItems {
+            key,
+            retention,
+            ids,
+            fresh: Vec::new(),
+            claimed: Vec::new(),
+        })
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.fresh
+            .iter()
+            .chain(self.claimed.iter())
+            .chain(self.ids.iter())
+            .any(|seen| seen == id)
+    }
+
+    // Returns false if the id has been seen before.
+    pub fn insert(&mut self, id: impl Into<String>) -> bool {
+        let id = id.into();
+        if self.contains(&id) {
+            return false;
+        }
+        self.fresh.push(id);
+
+        true
+    }
+
+    // For an id still in the feed, so it's kept ahead of those which left it.
+    pub fn keep(&mut self, id: impl Into<String>) {
+        let id = id.into();
+        if self.claimed.contains(&id) || self.fresh.contains(&id) {
+            return;
+        }
+        self.ids.retain(|seen| *seen != id);
+        self.fresh.push(id);
+    }
+
+    // For an item about to be emitted. The id counts as seen for the rest of
+    // this fetch, but is only saved by committing the returned change once
+    // the item has been sent. None if the id has been seen before.
+    pub fn claim(&mut self, id: impl Into<String>) -> Option<StateChange> {
+        let id = id.into();
+        if self.contains(&id) {
+            return None;
+        }
+        self.claimed.push(id.clone());
+
+        Some(StateChange::Seen {
+            key: self.key,
+            id,
+            retention: self.retention,
+        })
+    }
+
+    pub fn save(mut self, state: &mut State) -> Result<()> {
+        for id in self.fresh.drain(..).rev() {
+            self.ids.push_front(id);
+        }
+        self.ids.truncate(self.retention);
+
+        state.insert(self.key.to_string(), serde_json::to_string(&self.ids)?);
+
+        Ok(())
+    }
+}
+
+// Turns the outputs of a fetch into news: those whose id hasn't been seen, each
+// saving its id once it has been sent. Outputs from `sent_from` on were sent
+// before their ids were kept, and are only remembered.
+pub(crate) fn claim_unseen<O: Indexable + 'static>(
+    state: &mut State,
+    key: &'static str,
+    retention: usize,
+    outputs: Vec<O>,
+    sent_from: Option<usize>,
+    id: impl Fn(&O) -> String,
+) -> Result<Vec<FeedItem>> {
+    // Forgetting outputs still in the feed would send them again.
+    let retention = retention.max(outputs.len());
+    let mut seen = SeenItems::load(state, key, retention)?;
+
+    let mut news = Vec::new();
+    for (idx, output) in outputs.into_iter().enumerate() {
+        let id = id(&output);
+        if sent_from.is_some_and(|from| idx >= from) || seen.contains(&id) {
+            seen.keep(id);
+        } else if let Some(change) = seen.claim(id) {
+            news.push(FeedItem::new(output).with_change(change));
+        }
+    }
+
+    seen.save(state)?;
+
+    Ok(news)
+}
+
+// A stable id for items which carry no identity of their own.
+pub(crate) fn hash_id(parts: &[&str]) -> String {
+    let digest = digest(&SHA256, parts.join("\n").as_bytes());
+    digest
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_seen {
+    use super::*;
+    use crate::test_server::TestItem;
+    use crate::FeedItem;
+
+    #[test]
+    fn test_retention() {
+        let mut state = State::new();
+        let mut seen = SeenItems::load(&state, "seen", 3).unwrap();
+        assert!(seen.insert("a"));
+        assert!(seen.insert("b"));
+        assert!(!seen.insert("a"));
+        seen.save(&mut state).unwrap();
+
+        let mut seen = SeenItems::load(&state, "seen", 3).unwrap();
+        assert!(seen.contains("a"));
+        assert!(seen.insert("c"));
+        assert!(seen.insert("d"));
+        seen.save(&mut state).unwrap();
+
+        assert_eq!(state["seen"], r#"["c","d","a"]"#);
+    }
+
+    #[test]
+    fn test_claim() {
+        let mut state = State::new();
+        let mut seen = SeenItems::load(&state, "seen", 3).unwrap();
+        let change = seen.claim("a").unwrap();
+        assert!(seen.claim("a").is_none());
+        seen.save(&mut state).unwrap();
+        assert_eq!(state["seen"], "[]");
+
+        FeedItem::new(TestItem::new(&[]))
+            .with_change(change)
+            .commit(&mut state)
+            .unwrap();
+        assert_eq!(state["seen"], r#"["a"]"#);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::template::Template;
+use crate::web::{check_api_code, ensure_success};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub const SERVERCHAN_API_URL: &str = "https://sctapi.ftqq.com";
+
+// Pushes through ServerChan Turbo, the mapped text is its markdown `desp`.
+pub struct ServerChanSink {
+    config: ServerChanConfig,
+}
+
+pub struct ServerChanConfig {
+    pub api_url: String,
+    pub send_key: String,
+    pub title: Template,
+}
+
+impl ServerChanSink {
+    pub fn new(config: ServerChanConfig) -> Self {
+        ServerChanSink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for ServerChanSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let title = config.title.render(item);
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&format!("{}/{}.send", config.api_url, config.send_key))
+            .form(&[("title", title), ("desp", input)])
+            .send()
+            .await?;
+
+        let body = ensure_success(res).await?;
+        check_api_code("ServerChan", &body, "code", "0", "message")
+    }
+}
+
+#[cfg(test)]
+mod test_serverchan {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use crate::web::ApiError;
+    use async_std::task;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"code": 0, "message": ""}"#),
+            TestResponse::new(200, r#"{"code": 40001, "message": "bad pushtoken"}"#),
+        ]);
+        let sink = ServerChanSink::new(ServerChanConfig {
+            api_url: server.url.clone(),
+            send_key: "SCT123".to_string(),
+            title: "{title}".parse().unwrap(),
+        });
+        let item = TestItem::new(&[("title", "News & more")]);
+
+        task::block_on(sink.sink("**hi**".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/SCT123.send");
+        assert_eq!(
+            request.headers["content-type"],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(request.body, "title=News+%26+more&desp=**hi**");
+
+        let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+        let err = err.downcast::<ApiError>().unwrap();
+        assert_eq!(err.code, "40001");
+        assert_eq!(err.message, "bad pushtoken");
+    }
+}
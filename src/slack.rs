@@ -0,0 +1,133 @@
+use crate::web::{ensure_success, send_with_retry, split_text};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+const TEXT_LIMIT: usize = 40000;
+const SECTION_LIMIT: usize = 3000;
+const BLOCKS_LIMIT: usize = 50;
+
+// Posts to a Slack incoming webhook.
+pub struct SlackSink {
+    config: SlackConfig,
+}
+
+pub struct SlackConfig {
+    pub webhook_url: String,
+    pub username: Option<String>,
+    pub icon_emoji: Option<String>,
+    pub icon_url: Option<String>,
+    // Sends the text as `mrkdwn` section blocks rather than plain `text`.
+    pub blocks: bool,
+}
+
+impl SlackSink {
+    pub fn new(config: SlackConfig) -> Self {
+        SlackSink { config }
+    }
+
+    fn payloads(&self, input: &str) -> Vec<Value> {
+        let config = &self.config;
+        let mut payloads: Vec<Value> = if config.blocks {
+            let sections: Vec<_> = split_text(input, SECTION_LIMIT)
+                .into_iter()
+                .map(|text| json!({"type": "section", "text": {"type": "mrkdwn", "text": text}}))
+                .collect();
+            sections
+                .chunks(BLOCKS_LIMIT)
+                .map(|blocks| {
+                    // `text` is the fallback shown in notifications.
+                    let text = blocks[0]["text"]["text"].clone();
+                    json!({"text": text, "blocks": blocks})
+                })
+                .collect()
+        } else {
+            split_text(input, TEXT_LIMIT)
+                .into_iter()
+                .map(|text| json!({ "text": text }))
+                .collect()
+        };
+
+        for payload in payloads.iter_mut() {
+            if let Some(ref username) = config.username {
+                payload["username"] = username.as_str().into();
+            }
+            if let Some(ref icon_emoji) = config.icon_emoji {
+                payload["icon_emoji"] = icon_emoji.as_str().into();
+            }
+            if let Some(ref icon_url) = config.icon_url {
+                payload["icon_url"] = icon_url.as_str().into();
+            }
+        }
+
+        payloads
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for SlackSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let client = reqwest::Client::new();
+        for payload in self.payloads(&input) {
+            let request = client.post(&self.config.webhook_url).json(&payload);
+            ensure_success(send_with_retry(request).await?).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_slack {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+
+    fn sink(server: &TestServer, blocks: bool) -> SlackSink {
+        SlackSink::new(SlackConfig {
+            webhook_url: format!("{}/services/T0/B0/x", server.url),
+            username: Some("bot".to_string()),
+            icon_emoji: Some(":rss:".to_string()),
+            icon_url: None,
+            blocks,
+        })
+    }
+
+    #[test]
+    fn test_text() {
+        let server = TestServer::serve(vec![TestResponse::new(200, "ok")]);
+        let item = TestItem::new(&[]);
+
+        task::block_on(sink(&server, false).sink("*New* post".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/services/T0/B0/x");
+        assert_eq!(request.headers["content-type"], "application/json");
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            payload,
+            json!({"text": "*New* post", "username": "bot", "icon_emoji": ":rss:"})
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let server = TestServer::serve(vec![TestResponse::new(200, "ok")]);
+        let item = TestItem::new(&[]);
+
+        task::block_on(sink(&server, true).sink("*New* post".to_string(), &item)).unwrap();
+        let payload: Value = serde_json::from_str(&server.request().body).unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "text": "*New* post",
+                "blocks": [
+                    {"type": "section", "text": {"type": "mrkdwn", "text": "*New* post"}}
+                ],
+                "username": "bot",
+                "icon_emoji": ":rss:"
+            })
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::ConfigError;
+use crate::template::Template;
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_std::task;
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::str::FromStr;
+
+// Sends every mapped item as an email.
+pub struct SmtpSink {
+    config: SmtpConfig,
+}
+
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub credentials: Option<(String, String)>,
+    pub from: Mailbox,
+    pub to: Vec<Mailbox>,
+    pub cc: Vec<Mailbox>,
+    pub subject: Template,
+    pub html: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmtpSecurity {
+    // Upgrades a plain connection, usually on port 587.
+    StartTls,
+    // Implicit TLS, usually on port 465.
+    Tls,
+    None,
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = ConfigError;
+    fn from_str(security: &str) -> Result<Self, Self::Err> {
+        match security {
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" => Ok(SmtpSecurity::Tls),
+            "none" => Ok(SmtpSecurity::None),
+            _ => Err(ConfigError::UnsupportedValue(security.to_string())),
+        }
+    }
+}
+
+impl SmtpSecurity {
+    pub fn default_port(self) -> u16 {
+        match self {
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        }
+    }
+}
+
+pub fn parse_mailboxes(addresses: &[String]) -> Result<Vec<Mailbox>> {
+    Ok(addresses
+        .iter()
+        .map(|address| address.parse())
+        .collect::<Result<_, _>>()?)
+}
+
+impl SmtpSink {
+    pub fn new(config: SmtpConfig) -> Self {
+        SmtpSink { config }
+    }
+
+    fn transport(&self) -> Result<SmtpTransport> {
+        let config = &self.config;
+        let builder = match config.security {
+            SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&config.host)?,
+            SmtpSecurity::Tls => SmtpTransport::relay(&config.host)?,
+            SmtpSecurity::None => SmtpTransport::builder_dangerous(&config.host),
+        };
+        let builder = match config.credentials {
+            Some((ref username, ref password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            None => builder,
+        };
+
+        Ok(builder.port(config.port).build())
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for SmtpSink {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let subject = config.subject.render(item);
+
+        let mut builder = Message::builder()
+            .from(config.from.clone())
+            .subject(subject);
+        for to in config.to.iter() {
+            builder = builder.to(to.clone());
+        }
+        for cc in config.cc.iter() {
+            builder = builder.cc(cc.clone());
+        }
+        let content_type = if config.html {
+            ContentType::TEXT_HTML
+        } else {
+            ContentType::TEXT_PLAIN
+        };
+        let email = builder.header(content_type).body(input)?;
+
+        let transport = self.transport()?;
+        task::spawn_blocking(move || transport.send(&email)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_smtp {
+    use super::*;
+    use crate::test_server::TestItem;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    // Accepts a single mail and hands over what was sent after DATA.
+    fn serve_smtp() -> (u16, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let command = line.trim_end().to_uppercase();
+                line.clear();
+                if command.starts_with("EHLO") {
+                    writer
+                        .write_all(b"250-localhost\r\n250 8BITMIME\r\n")
+                        .unwrap();
+                } else if command == "DATA" {
+                    writer.write_all(b"354 Go ahead\r\n").unwrap();
+                    let mut data = String::new();
+                    while data.is_empty() || !data.ends_with("\r\n.\r\n") {
+                        reader.read_line(&mut data).unwrap();
+                    }
+                    sender.send(data).unwrap();
+                    writer.write_all(b"250 Queued\r\n").unwrap();
+                } else if command == "QUIT" {
+                    writer.write_all(b"221 Bye\r\n").unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 OK\r\n").unwrap();
+                }
+            }
+        });
+
+        (port, receiver)
+    }
+
+    #[test]
+    fn test_sink() {
+        let (port, mails) = serve_smtp();
+        let sink = SmtpSink::new(SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            credentials: None,
+            from: "Bot <bot@example.com>".parse().unwrap(),
+            to: parse_mailboxes(&["a@example.com".to_string()]).unwrap(),
+            cc: parse_mailboxes(&["b@example.com".to_string()]).unwrap(),
+            subject: "New: {title}".parse().unwrap(),
+            html: false,
+        });
+        let item = TestItem::new(&[("title", "Release 1.0")]);
+
+        task::block_on(sink.sink("Hello".to_string(), &item)).unwrap();
+
+        let mail = mails.recv().unwrap();
+        assert!(mail.contains("Subject: New: Release 1.0\r\n"));
+        assert!(mail.contains("To: a@example.com\r\n"));
+        assert!(mail.contains("Cc: b@example.com\r\n"));
+        assert!(mail.contains("Content-Type: text/plain"));
+        assert!(mail.contains("\r\n\r\nHello\r\n"));
+    }
+}
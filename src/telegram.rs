@@ -0,0 +1,470 @@
+use crate::config::ConfigError;
+use crate::filter::Filter;
+use crate::web::{send_with_retry, split_text};
+use crate::{Indexable, Sink};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+
+pub const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+const MESSAGE_LIMIT: usize = 4096;
+// Longest first, so `__` isn't read as two `_`.
+const MARKDOWN_V2_MARKERS: [&str; 7] = ["```", "||", "__", "`", "*", "_", "~"];
+
+pub struct TelegramSink {
+    config: TelegramConfig,
+}
+
+pub struct TelegramConfig {
+    pub api_url: String,
+    pub bot_token: String,
+    pub chat_id: String,
+    pub parse_mode: Option<ParseMode>,
+    pub disable_preview: bool,
+    pub silent: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseMode {
+    MarkdownV2,
+    Html,
+}
+
+impl FromStr for ParseMode {
+    type Err = ConfigError;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "MarkdownV2" => Ok(ParseMode::MarkdownV2),
+            "HTML" => Ok(ParseMode::Html),
+            _ => Err(ConfigError::UnsupportedValue(mode.to_string())),
+        }
+    }
+}
+
+impl ParseMode {
+    fn name(self) -> &'static str {
+        match self {
+            ParseMode::MarkdownV2 => "MarkdownV2",
+            ParseMode::Html => "HTML",
+        }
+    }
+
+    fn pieces(self, text: &str) -> Vec<Piece<'_>> {
+        match self {
+            ParseMode::MarkdownV2 => markdown_v2_pieces(text),
+            ParseMode::Html => html_pieces(text),
+        }
+    }
+}
+
+// A part of a marked-up text which a message is never cut in.
+struct Piece<'a> {
+    text: &'a str,
+    kind: PieceKind,
+}
+
+enum PieceKind {
+    Text,
+    // Starts an entity, which the markup in the variant ends.
+    Open(String),
+    Close,
+}
+
+// Tags and character references are pieces, so is every other char.
+fn html_pieces(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '<' => rest.find('>').map(|end| end + 1),
+            '&' => rest.find(';').filter(|&end| end <= 10).map(|end| end + 1),
+            _ => None,
+        }
+        .unwrap_or_else(|| c.len_utf8());
+        let (text, tail) = rest.split_at(len);
+        rest = tail;
+
+        let kind = match text.strip_prefix('<') {
+            Some(tag) if tag.starts_with('/') => PieceKind::Close,
+            Some(tag) if len > 1 => {
+                let name: String = tag
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                    .collect();
+                PieceKind::Open(format!("</{}>", name))
+            }
+            _ => PieceKind::Text,
+        };
+        pieces.push(Piece { text, kind });
+    }
+    pieces
+}
+
+// Escapes, links and entity markers are pieces, so is every other char.
+fn markdown_v2_pieces(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        // Only the closing backticks mean something in code.
+        let in_code = matches!(open.last(), Some(&"`") | Some(&"```"));
+        let marker = MARKDOWN_V2_MARKERS
+            .iter()
+            .copied()
+            .filter(|marker| !in_code || open.last() == Some(marker))
+            .find(|marker| rest.starts_with(marker));
+
+        let (len, kind) = match marker {
+            _ if c == '\\' => (1 + rest[1..].chars().next().map_or(0, char::len_utf8), None),
+            Some(marker) if open.last() == Some(&marker) => {
+                open.pop();
+                (marker.len(), Some(PieceKind::Close))
+            }
+            Some(marker) => {
+                open.push(marker);
+                // A code block keeps its language line when opened again.
+                let language = match marker {
+                    "```" => {
+                        let len = rest[3..]
+                            .find(|c: char| !c.is_ascii_alphanumeric())
+                            .unwrap_or(rest.len() - 3);
+                        match &rest[3 + len..] {
+                            line if len > 0 && line.starts_with('\n') => len + 1,
+                            _ => len,
+                        }
+                    }
+                    _ => 0,
+                };
+                let end = marker.to_string();
+                (marker.len() + language, Some(PieceKind::Open(end)))
+            }
+            None if c == '[' && !in_code => (link_len(rest).unwrap_or(1), None),
+            None => (c.len_utf8(), None),
+        };
+        let (text, tail) = rest.split_at(len);
+        rest = tail;
+        let kind = kind.unwrap_or(PieceKind::Text);
+        pieces.push(Piece { text, kind });
+    }
+    pieces
+}
+
+// The length of a `[text](url)` link at the start of the text.
+fn link_len(text: &str) -> Option<usize> {
+    let label = find_unescaped(text, ']')?;
+    if !text[label + 1..].starts_with('(') {
+        return None;
+    }
+    let url = find_unescaped(&text[label + 1..], ')')?;
+    Some(label + 1 + url + 1)
+}
+
+fn find_unescaped(text: &str, target: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == target => return Some(idx),
+            _ => (),
+        }
+    }
+    None
+}
+
+// The text of a message being split, see `split_markup`.
+#[derive(Default)]
+struct Chunk {
+    text: String,
+    chars: usize,
+    // The entities open at the end, as the markup starting and ending them.
+    open: Vec<(String, String)>,
+    // Where the text after the entities opened again starts.
+    start: usize,
+    // Where the last char shown ends, so no message is only markup.
+    shown: usize,
+    // The last line break after `start`, with the entities open there.
+    newline: Option<(usize, Vec<(String, String)>)>,
+}
+
+impl Chunk {
+    fn has_text(&self) -> bool {
+        self.shown > self.start
+    }
+
+    // How long the chunk gets with the piece and the markup ending what's open.
+    fn len_with(&self, piece: &Piece) -> usize {
+        let closing: usize = self.open.iter().map(|(_, end)| end.chars().count()).sum();
+        let closing = match piece.kind {
+            PieceKind::Text => closing,
+            PieceKind::Open(ref end) => closing + end.chars().count(),
+            PieceKind::Close => {
+                closing - self.open.last().map_or(0, |(_, end)| end.chars().count())
+            }
+        };
+        self.chars + piece.text.chars().count() + closing
+    }
+
+    fn push(&mut self, piece: &Piece) {
+        if piece.text == "\n" && self.has_text() {
+            self.newline = Some((self.text.len(), self.open.clone()));
+        }
+        // Entities opened again right before they end would be empty.
+        if let (PieceKind::Close, Some((start, _))) = (&piece.kind, self.open.last()) {
+            if self.text.ends_with(start.as_str()) {
+                self.text.truncate(self.text.len() - start.len());
+                self.chars -= start.chars().count();
+                self.start = self.start.min(self.text.len());
+                self.open.pop();
+                return;
+            }
+        }
+
+        self.text.push_str(piece.text);
+        self.chars += piece.text.chars().count();
+        match piece.kind {
+            PieceKind::Text if piece.text != "\n" => self.shown = self.text.len(),
+            PieceKind::Text => (),
+            PieceKind::Open(ref end) => self.open.push((piece.text.to_string(), end.clone())),
+            PieceKind::Close => {
+                self.open.pop();
+            }
+        }
+    }
+
+    // Cuts at the last line break where possible, else at the end. Returns the
+    // finished message and the start of the next one.
+    fn cut(self) -> (String, Chunk) {
+        let (at, skip, open_at) = match self.newline {
+            Some((at, open_at)) => (at, 1, open_at),
+            None => (self.text.len(), 0, self.open.clone()),
+        };
+        let mut message = self.text[..at].to_string();
+        message.extend(open_at.iter().rev().map(|(_, end)| end.as_str()));
+
+        // Entities ending right at the cut aren't started again empty.
+        let mut reopen = open_at.as_slice();
+        let mut tail = at + skip;
+        while let Some(((_, end), rest)) = reopen.split_last() {
+            if !self.text[tail..].starts_with(end.as_str()) {
+                break;
+            }
+            reopen = rest;
+            tail += end.len();
+        }
+
+        let mut text: String = reopen.iter().map(|(start, _)| start.as_str()).collect();
+        let start = text.len();
+        text.push_str(&self.text[tail..]);
+        let shown = (self.shown + start).saturating_sub(tail);
+        let next = Chunk {
+            chars: text.chars().count(),
+            text,
+            open: self.open,
+            start,
+            shown,
+            newline: None,
+        };
+        (message, next)
+    }
+}
+
+// Like `split_text`, but never cuts inside a piece, and the entities open at a
+// cut are ended and started again in the next message.
+fn split_markup(pieces: &[Piece], limit: usize) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut chunk = Chunk::default();
+    for piece in pieces {
+        while chunk.len_with(piece) > limit && chunk.has_text() {
+            let (message, next) = chunk.cut();
+            messages.push(message);
+            chunk = next;
+        }
+        chunk.push(piece);
+    }
+    messages.push(chunk.text);
+    messages
+}
+
+#[derive(Deserialize)]
+struct TelegramResponse {
+    ok: bool,
+    #[serde(default)]
+    description: String,
+}
+
+impl TelegramSink {
+    pub fn new(config: TelegramConfig) -> Self {
+        TelegramSink { config }
+    }
+
+    // Messages over the limit are sent as several ones.
+    fn messages(&self, input: &str) -> Vec<String> {
+        match self.config.parse_mode {
+            Some(mode) => split_markup(&mode.pieces(input), MESSAGE_LIMIT),
+            None => split_text(input, MESSAGE_LIMIT),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for TelegramSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
+        let client = reqwest::Client::new();
+        let url = format!("{}/bot{}/sendMessage", config.api_url, config.bot_token);
+
+        for text in self.messages(&input) {
+            let mut message = json!({
+                "chat_id": config.chat_id,
+                "text": text,
+                "disable_web_page_preview": config.disable_preview,
+                "disable_notification": config.silent,
+            });
+            if let Some(mode) = config.parse_mode {
+                message["parse_mode"] = mode.name().into();
+            }
+
+            let res = send_with_retry(client.post(&url).json(&message)).await?;
+            let status = res.status();
+            let res: TelegramResponse = res.json().await?;
+            if !res.ok {
+                return Err(anyhow!("Telegram error {}: {}", status, res.description));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn value_escape(&self) -> Option<Filter> {
+        match self.config.parse_mode? {
+            ParseMode::MarkdownV2 => Some(Filter::MarkdownEscape),
+            ParseMode::Html => Some(Filter::HtmlEscape),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_telegram {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(429, r#"{"ok": false, "parameters": {"retry_after": 0}}"#),
+            TestResponse::new(200, r#"{"ok": true}"#),
+            TestResponse::new(400, r#"{"ok": false, "description": "chat not found"}"#),
+        ]);
+        let sink = TelegramSink::new(TelegramConfig {
+            api_url: server.url.clone(),
+            bot_token: "123:abc".to_string(),
+            chat_id: "42".to_string(),
+            parse_mode: Some(ParseMode::MarkdownV2),
+            disable_preview: true,
+            silent: false,
+        });
+        let item = TestItem::new(&[]);
+
+        let input = r"*v1\.0* \(beta\)\!";
+        task::block_on(sink.sink(input.to_string(), &item)).unwrap();
+        server.request();
+        let request = server.request();
+        assert_eq!(request.path, "/bot123:abc/sendMessage");
+        let message: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(message["text"], input);
+        assert_eq!(message["parse_mode"], "MarkdownV2");
+        assert_eq!(message["disable_web_page_preview"], true);
+
+        let err = task::block_on(sink.sink("hi".to_string(), &item)).unwrap_err();
+        assert!(err.to_string().contains("chat not found"));
+    }
+
+    // Every entity ends in the message it starts in, and no escape is cut off.
+    fn assert_valid(mode: ParseMode, messages: &[String], limit: usize) {
+        for message in messages {
+            assert!(
+                message.chars().count() <= limit,
+                "{:?} is too long",
+                message
+            );
+            let mut open = Vec::new();
+            for piece in mode.pieces(message) {
+                match piece.kind {
+                    PieceKind::Text => assert_ne!(piece.text, "\\", "{:?}", message),
+                    PieceKind::Open(end) => open.push(end),
+                    PieceKind::Close => assert_eq!(open.pop().as_deref(), Some(piece.text)),
+                }
+            }
+            assert!(open.is_empty(), "{:?} leaves {:?} open", message, open);
+        }
+    }
+
+    // The text shown, cuts only drop line breaks.
+    fn visible(mode: ParseMode, text: &str) -> String {
+        mode.pieces(text)
+            .into_iter()
+            .filter(|piece| matches!(piece.kind, PieceKind::Text) && piece.text != "\n")
+            .map(|piece| piece.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_split_html() {
+        let mode = ParseMode::Html;
+        let input = format!(
+            "<b>{}</b><a href=\"https://example.com\">{}</a>",
+            "x &amp; y\n".repeat(5),
+            "link ".repeat(6)
+        );
+
+        let messages = split_markup(&mode.pieces(&input), 40);
+        assert_valid(mode, &messages, 40);
+        assert_eq!(messages[0], "<b>x &amp; y\nx &amp; y\nx &amp; y</b>");
+        assert_eq!(messages[1], "<b>x &amp; y\nx &amp; y</b>");
+        assert!(messages.last().unwrap().starts_with("<a href="));
+        assert_eq!(visible(mode, &messages.concat()), visible(mode, &input));
+    }
+
+    #[test]
+    fn test_split_markdown_v2() {
+        let mode = ParseMode::MarkdownV2;
+        let input = format!(
+            "*{}* ```rust\n{}``` [a \\] link](https://example.com)",
+            r"1\.5 ".repeat(10),
+            "let a = 1;".repeat(3)
+        );
+
+        let messages = split_markup(&mode.pieces(&input), 40);
+        assert_valid(mode, &messages, 40);
+        assert_eq!(messages[0], r"*1\.5 1\.5 1\.5 1\.5 1\.5 1\.5 1\.5 1\.*");
+        assert_eq!(messages[2], "```rust\na = 1;let a = 1;``` ");
+        assert_eq!(
+            messages.last().unwrap(),
+            r"[a \] link](https://example.com)"
+        );
+        assert_eq!(visible(mode, &messages.concat()), visible(mode, &input));
+    }
+
+    #[test]
+    fn test_messages() {
+        let sink = TelegramSink::new(TelegramConfig {
+            api_url: String::new(),
+            bot_token: String::new(),
+            chat_id: String::new(),
+            parse_mode: Some(ParseMode::Html),
+            disable_preview: false,
+            silent: false,
+        });
+
+        let input = format!("<b>{}</b>", "a".repeat(MESSAGE_LIMIT));
+        let messages = sink.messages(&input);
+        assert_eq!(messages.len(), 2);
+        assert_valid(ParseMode::Html, &messages, MESSAGE_LIMIT);
+        assert_eq!(sink.value_escape(), Some(Filter::HtmlEscape));
+    }
+}
@@ -0,0 +1,559 @@
+use crate::filter::Filter;
+use crate::Indexable;
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+// A template rendered with the fields of an item:
+//
+//   {title}                            the field, empty if it's missing
+//   {summary ?? description ?? "-"}    the first non-empty value
+//   {#if author}by {author}{#else}anonymous{/if}
+//   {#each categories}#{.} {/each}     `categories.0`, `categories.1`...
+//   {title | truncate(80) | upper}     filters, see `Filter`
+//
+// `\{`, `\}` and `\\` stand for the characters themselves.
+//
+// Besides the text mapper, sinks use templates for config values such as a
+// title or a topic, rendered with the item being sent.
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+    // Applied to every value substituted into the output, after its own filters.
+    escape: Option<Filter>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Expr(Expr),
+    If {
+        cond: Expr,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        list: String,
+        body: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Expr {
+    operands: Vec<Operand>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Field(String),
+    // The element of the innermost `#each`.
+    Current,
+    Literal(String),
+}
+
+#[derive(Debug)]
+pub struct TemplateError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}.",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+            each_depth: 0,
+        };
+        match parser.parse_nodes()? {
+            (nodes, None) => Ok(Template {
+                nodes,
+                escape: None,
+            }),
+            (_, Some((tag, at))) => Err(parser.error(at, format!("Unexpected `{}`", tag.name()))),
+        }
+    }
+}
+
+impl Template {
+    // A template rendering the text as is.
+    pub fn literal(text: impl Into<String>) -> Self {
+        Template {
+            nodes: vec![Node::Text(text.into())],
+            escape: None,
+        }
+    }
+
+    // For output in a markup language: the text of the template is kept as
+    // markup, while values can't break it. Values whose last filter is already
+    // `escape` aren't escaped twice.
+    pub fn escape_values(&mut self, escape: Filter) {
+        self.escape = Some(escape);
+    }
+
+    pub fn render(&self, item: &dyn Indexable) -> String {
+        let mut output = String::new();
+        let escape = self.escape.as_ref();
+        render_nodes(&self.nodes, item, escape, &mut Vec::new(), &mut output);
+        output
+    }
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    item: &'a dyn Indexable,
+    escape: Option<&Filter>,
+    scope: &mut Vec<&'a str>,
+    output: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Expr(expr) => {
+                let value = expr.eval(item, scope);
+                match escape {
+                    Some(escape) if expr.filters.last() != Some(escape) => {
+                        output.push_str(&escape.apply(&value))
+                    }
+                    _ => output.push_str(&value),
+                }
+            }
+            Node::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let branch = if cond.eval(item, scope).is_empty() {
+                    otherwise
+                } else {
+                    then
+                };
+                render_nodes(branch, item, escape, scope, output);
+            }
+            Node::Each { list, body } => {
+                for idx in 0.. {
+                    let element = &item[format!("{}.{}", list, idx).as_str()];
+                    if element.is_empty() {
+                        break;
+                    }
+                    scope.push(element);
+                    render_nodes(body, item, escape, scope, output);
+                    scope.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Expr {
+    fn eval<'a>(&'a self, item: &'a dyn Indexable, scope: &[&'a str]) -> Cow<'a, str> {
+        let value = self
+            .operands
+            .iter()
+            .map(|operand| match operand {
+                Operand::Field(name) => &item[name.as_str()],
+                Operand::Current => scope.last().copied().unwrap_or_default(),
+                Operand::Literal(text) => text.as_str(),
+            })
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+
+        self.filters
+            .iter()
+            .fold(Cow::Borrowed(value), |value, filter| {
+                Cow::Owned(filter.apply(&value))
+            })
+    }
+}
+
+enum Tag {
+    Expr(Expr),
+    If(Expr),
+    Else,
+    EndIf,
+    Each(String),
+    EndEach,
+}
+
+impl Tag {
+    fn name(&self) -> &'static str {
+        match self {
+            Tag::Expr(_) => "{...}",
+            Tag::If(_) => "{#if}",
+            Tag::Else => "{#else}",
+            Tag::EndIf => "{/if}",
+            Tag::Each(_) => "{#each}",
+            Tag::EndEach => "{/each}",
+        }
+    }
+}
+
+// A tag ending a block and its position.
+type End = (Tag, usize);
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Coalesce,
+    Pipe,
+    LParen,
+    RParen,
+    Comma,
+    Close,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    each_depth: usize,
+}
+
+impl Parser {
+    fn error(&self, at: usize, message: impl Into<String>) -> TemplateError {
+        let before = &self.chars[..at.min(self.chars.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |idx| idx + 1);
+        TemplateError {
+            line: before.iter().filter(|&&c| c == '\n').count() + 1,
+            column: at - line_start + 1,
+            message: message.into(),
+        }
+    }
+
+    // Parses until the end, or until a tag closing the enclosing block.
+    fn parse_nodes(&mut self) -> Result<(Vec<Node>, Option<End>), TemplateError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            if c == '\\' {
+                if let Some(&escaped @ ('{' | '}' | '\\')) = self.chars.get(self.pos + 1) {
+                    text.push(escaped);
+                    self.pos += 2;
+                    continue;
+                }
+            }
+            if c != '{' {
+                text.push(c);
+                self.pos += 1;
+                continue;
+            }
+
+            if !text.is_empty() {
+                nodes.push(Node::Text(std::mem::take(&mut text)));
+            }
+            let start = self.pos;
+            let node = match self.parse_tag()? {
+                Tag::Expr(expr) => Node::Expr(expr),
+                Tag::If(cond) => self.parse_if(cond, start)?,
+                Tag::Each(list) => self.parse_each(list, start)?,
+                tag => return Ok((nodes, Some((tag, start)))),
+            };
+            nodes.push(node);
+        }
+
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok((nodes, None))
+    }
+
+    fn parse_if(&mut self, cond: Expr, start: usize) -> Result<Node, TemplateError> {
+        let (then, end) = self.parse_nodes()?;
+        let otherwise = match end {
+            Some((Tag::EndIf, _)) => Vec::new(),
+            Some((Tag::Else, _)) => match self.parse_nodes()? {
+                (otherwise, Some((Tag::EndIf, _))) => otherwise,
+                (_, end) => return Err(self.unclosed("{#if}", start, end)),
+            },
+            end => return Err(self.unclosed("{#if}", start, end)),
+        };
+
+        Ok(Node::If {
+            cond,
+            then,
+            otherwise,
+        })
+    }
+
+    fn parse_each(&mut self, list: String, start: usize) -> Result<Node, TemplateError> {
+        self.each_depth += 1;
+        let (body, end) = self.parse_nodes()?;
+        self.each_depth -= 1;
+
+        match end {
+            Some((Tag::EndEach, _)) => Ok(Node::Each { list, body }),
+            end => Err(self.unclosed("{#each}", start, end)),
+        }
+    }
+
+    fn unclosed(&self, block: &str, start: usize, end: Option<End>) -> TemplateError {
+        match end {
+            Some((tag, at)) => {
+                self.error(at, format!("Unexpected `{}` in `{}`", tag.name(), block))
+            }
+            None => self.error(start, format!("Unclosed `{}`", block)),
+        }
+    }
+
+    fn parse_tag(&mut self) -> Result<Tag, TemplateError> {
+        // Skips the `{`.
+        self.pos += 1;
+        let (token, at) = self.peek_token()?;
+        let keyword = match token {
+            Token::Word(ref word) if word.starts_with('#') || word.starts_with('/') => {
+                self.next_token()?;
+                Some(word.clone())
+            }
+            _ => None,
+        };
+
+        let tag = match keyword.as_deref() {
+            None => Tag::Expr(self.parse_expr()?),
+            Some("#if") => Tag::If(self.parse_expr()?),
+            Some("#else") => Tag::Else,
+            Some("/if") => Tag::EndIf,
+            Some("#each") => match self.next_token()? {
+                (Token::Word(list), _) if list != "." => Tag::Each(list),
+                (_, at) => return Err(self.error(at, "Expected a list field")),
+            },
+            Some("/each") => Tag::EndEach,
+            Some(keyword) => return Err(self.error(at, format!("Unknown block `{}`", keyword))),
+        };
+
+        match self.next_token()? {
+            (Token::Close, _) => Ok(tag),
+            (_, at) => Err(self.error(at, "Expected `}`")),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, TemplateError> {
+        let mut operands = vec![self.parse_operand()?];
+        while self.peek_token()?.0 == Token::Coalesce {
+            self.next_token()?;
+            operands.push(self.parse_operand()?);
+        }
+        let mut filters = Vec::new();
+        while self.peek_token()?.0 == Token::Pipe {
+            self.next_token()?;
+            filters.push(self.parse_filter()?);
+        }
+
+        Ok(Expr { operands, filters })
+    }
+
+    fn parse_filter(&mut self) -> Result<Filter, TemplateError> {
+        let (name, at) = match self.next_token()? {
+            (Token::Word(name), at) => (name, at),
+            (_, at) => return Err(self.error(at, "Expected a filter")),
+        };
+
+        let mut args = Vec::new();
+        if self.peek_token()?.0 == Token::LParen {
+            self.next_token()?;
+            loop {
+                match self.next_token()? {
+                    (Token::Word(arg), _) | (Token::Str(arg), _) => args.push(arg),
+                    (Token::RParen, _) if args.is_empty() => break,
+                    (_, at) => return Err(self.error(at, "Expected an argument")),
+                }
+                match self.next_token()? {
+                    (Token::Comma, _) => (),
+                    (Token::RParen, _) => break,
+                    (_, at) => return Err(self.error(at, "Expected `,` or `)`")),
+                }
+            }
+        }
+
+        Filter::new(&name, &args).map_err(|message| self.error(at, message))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, TemplateError> {
+        match self.next_token()? {
+            (Token::Word(word), at) if word == "." => {
+                if self.each_depth == 0 {
+                    return Err(self.error(at, "`.` outside of `{#each}`"));
+                }
+                Ok(Operand::Current)
+            }
+            (Token::Word(word), _) => Ok(Operand::Field(word)),
+            (Token::Str(text), _) => Ok(Operand::Literal(text)),
+            (_, at) => Err(self.error(at, "Expected a field or a string")),
+        }
+    }
+
+    fn peek_token(&mut self) -> Result<(Token, usize), TemplateError> {
+        let pos = self.pos;
+        let token = self.next_token();
+        self.pos = pos;
+        token
+    }
+
+    fn next_token(&mut self) -> Result<(Token, usize), TemplateError> {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        let c = match self.chars.get(self.pos) {
+            Some(&c) => c,
+            None => return Err(self.error(start, "Expected `}`")),
+        };
+        self.pos += 1;
+
+        let token = match c {
+            '}' => Token::Close,
+            '|' => Token::Pipe,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match self.chars.get(self.pos) {
+                        Some('"') => break,
+                        Some('\\') if self.chars.get(self.pos + 1).is_some() => {
+                            text.push(self.chars[self.pos + 1]);
+                            self.pos += 2;
+                        }
+                        Some(&c) => {
+                            text.push(c);
+                            self.pos += 1;
+                        }
+                        None => return Err(self.error(start, "Unclosed string")),
+                    }
+                }
+                self.pos += 1;
+                Token::Str(text)
+            }
+            '?' if self.chars.get(self.pos) == Some(&'?') => {
+                self.pos += 1;
+                Token::Coalesce
+            }
+            c if is_word_char(c) => {
+                while self.chars.get(self.pos).is_some_and(|&c| is_word_char(c)) {
+                    self.pos += 1;
+                }
+                Token::Word(self.chars[start..self.pos].iter().collect())
+            }
+            c => return Err(self.error(start, format!("Unexpected `{}`", c))),
+        };
+
+        Ok((token, start))
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"{}\"?|(),".contains(c)
+}
+
+#[cfg(test)]
+mod test_template {
+    use super::*;
+    use crate::test_server::TestItem;
+
+    fn render(template: &str, fields: &[(&str, &str)]) -> String {
+        let template: Template = template.parse().unwrap();
+        template.render(&TestItem::new(fields))
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(r"\{{title}\} \\ {missing}", &[("title", "Hi")]),
+            r"{Hi} \ "
+        );
+        assert_eq!(
+            render(r#"{summary ?? description ?? "No \"text\""}"#, &[]),
+            r#"No "text""#
+        );
+        assert_eq!(
+            render(
+                "{#if author}by {author}{#else}anonymous{/if}",
+                &[("author", "Alice")]
+            ),
+            "by Alice"
+        );
+    }
+
+    #[test]
+    fn test_escape_values() {
+        let mut template: Template = "<b>{title}</b> {author | html_escape}".parse().unwrap();
+        template.escape_values(Filter::HtmlEscape);
+        let item = TestItem::new(&[("title", "Q&A <live>"), ("author", "Tom & Jerry")]);
+        assert_eq!(
+            template.render(&item),
+            "<b>Q&amp;A &lt;live&gt;</b> Tom &amp; Jerry"
+        );
+    }
+
+    #[test]
+    fn test_each() {
+        assert_eq!(
+            render(
+                "{#each tags}[{.}{#if media.0}+{/if}]{/each}",
+                &[("tags.0", "rust"), ("tags.1", "web"), ("media.0", "x")]
+            ),
+            "[rust+][web+]"
+        );
+        assert_eq!(render("{#each tags}{.}{/each}", &[]), "");
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(
+            render(
+                r#"{summary ?? title | truncate(8) | upper}: {link | urlencode}"#,
+                &[("title", "Release notes"), ("link", "a b")]
+            ),
+            "RELEASE…: a%20b"
+        );
+        assert_eq!(
+            render(
+                r#"{#each tags}{. | date("%d/%m", "UTC")} {/each}"#,
+                &[("tags.0", "2020-06-01T23:00:00-02:00")]
+            ),
+            "02/06 "
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |template: &str| template.parse::<Template>().unwrap_err().to_string();
+
+        assert_eq!(error("{title"), "Expected `}` at line 1, column 7.");
+        assert_eq!(
+            error("Hi\n  {#if title}{title}"),
+            "Unclosed `{#if}` at line 2, column 3."
+        );
+        assert_eq!(
+            error("{#each tags}{/if}"),
+            "Unexpected `{/if}` in `{#each}` at line 1, column 13."
+        );
+        assert_eq!(
+            error(r#"{title | truncate("a")}"#),
+            "Invalid length `a` at line 1, column 10."
+        );
+        assert_eq!(
+            error("{.}"),
+            "`.` outside of `{#each}` at line 1, column 2."
+        );
+        assert_eq!(error(r#"{"a": 1}"#), "Expected `}` at line 1, column 5.");
+    }
+}
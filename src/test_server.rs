@@ -0,0 +1,146 @@
+// A tiny HTTP server answering canned responses, so feeds and sinks can be
+// tested without touching the real services.
+#![allow(dead_code)]
+use crate::{Feed, FeedItem, State};
+use async_std::task;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub struct TestResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl TestResponse {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        TestResponse {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+pub struct TestServer {
+    pub url: String,
+    requests: Receiver<RecordedRequest>,
+}
+
+impl TestServer {
+    // Answers one connection per response, in order.
+    pub fn serve(responses: Vec<TestResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, requests) = mpsc::channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+                    }
+                }
+
+                let length = headers
+                    .get("content-length")
+                    .and_then(|length| length.parse().ok())
+                    .unwrap_or(0);
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let mut stream = reader.into_inner();
+                let mut head = format!(
+                    "HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in response.headers {
+                    head.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                head.push_str("\r\n");
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(response.body.as_bytes()).unwrap();
+                stream.flush().unwrap();
+
+                let request = RecordedRequest {
+                    method,
+                    path,
+                    headers,
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                };
+                if sender.send(request).is_err() {
+                    break;
+                }
+            }
+        });
+
+        TestServer { url, requests }
+    }
+
+    pub fn request(&self) -> RecordedRequest {
+        self.requests.recv().unwrap()
+    }
+}
+
+// A feed output with the given fields.
+pub struct TestItem(HashMap<String, String>);
+
+impl TestItem {
+    pub fn new(fields: &[(&str, &str)]) -> Self {
+        TestItem(
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl<'a> std::ops::Index<&'a str> for TestItem {
+    type Output = str;
+    fn index(&self, field: &'a str) -> &Self::Output {
+        self.0.get(field).map(String::as_str).unwrap_or_default()
+    }
+}
+
+// Runs a feed and commits every item, as if all of them were sent.
+pub fn feed_and_commit(feed: &impl Feed, state: &mut State) -> Vec<FeedItem> {
+    let news = task::block_on(feed.feed(state)).unwrap();
+    for item in &news {
+        item.commit(state).unwrap();
+    }
+    news
+}
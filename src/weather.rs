@@ -1,85 +1,292 @@
-use crate::{Feed, Indexable, State};
+use crate::condition::Condition;
+use crate::weather_provider::WeatherProvider;
+use crate::{Feed, FeedItem, State, StateChange};
 use anyhow::Result;
 use async_trait::async_trait;
-use rss::{Channel, Item};
 use serde::Deserialize;
+use std::fmt::{self, Display, Formatter};
 
-const WEATHER_URL: &str = "https://devapi.heweather.net/v7/weather/3d";
+pub const DEFAULT_MODE: &str = "3d";
+const WEATHER_ALERTED_DATE: &str = "weather_alerted_date";
 
 pub struct WeatherFeed {
     pub config: WeatherConfig,
 }
 
 impl WeatherFeed {
-    pub fn new(key: impl Into<String>, location: impl Into<String>) -> Self {
-        let config = WeatherConfig {
-            key: key.into(),
-            location: location.into(),
-        };
+    pub fn new(config: WeatherConfig) -> Self {
         WeatherFeed { config }
     }
 }
 
+#[derive(Debug)]
+pub enum WeatherError {
+    // The API answered with a code other than 200.
+    Api(String),
+    NoForecast,
+}
+
+impl Display for WeatherError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WeatherError::Api(code) => write!(f, "Weather API returned code {}.", code),
+            WeatherError::NoForecast => write!(f, "No weather found."),
+        }
+    }
+}
+
+impl std::error::Error for WeatherError {}
+
 #[async_trait]
 impl Feed for WeatherFeed {
-    async fn feed(&self, state: &mut State) -> Result<Vec<Box<dyn Indexable>>> {
+    async fn feed(&self, state: &mut State) -> Result<Vec<FeedItem>> {
         let client = reqwest::ClientBuilder::new().build()?;
+        let mut res = self.config.provider.fetch(&client, &self.config).await?;
+
+        if res.daily.is_empty() && res.hourly.is_empty() {
+            return Err(WeatherError::NoForecast.into());
+        }
+
+        if self.config.conditions.is_empty() {
+            return Ok(vec![FeedItem::new(res)]);
+        }
 
-        let res: WeatherOutput = client
-            .get(WEATHER_URL)
-            .query(&[
-                ("key", &self.config.key),
-                ("location", &self.config.location),
-            ])
-            .send()
-            .await?
-            .json()
-            .await?;
-        Ok(vec![Box::new(res)])
+        // Only alert once a day, however many runs see the same weather.
+        let date = res.date().to_string();
+        if state.get(WEATHER_ALERTED_DATE) == Some(&date) {
+            return Ok(Vec::new());
+        }
+
+        let fired: Vec<_> = self
+            .config
+            .conditions
+            .iter()
+            .filter(|condition| condition.matches(&res))
+            .map(Condition::rule)
+            .collect();
+        if fired.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        res.alerts = fired.join("; ");
+        let alerted = StateChange::Set(WEATHER_ALERTED_DATE.to_string(), date);
+
+        Ok(vec![FeedItem::new(res).with_change(alerted)])
     }
 }
 
-#[derive(Deserialize)]
 pub struct WeatherConfig {
     pub key: String,
     pub location: String,
+    // `3d`, `7d`... for daily forecasts and `24h`, `72h`... for hourly ones.
+    pub mode: String,
+    pub provider: WeatherProvider,
+    pub base_url: String,
+    // Emit only when one of these fires, see `Condition`.
+    pub conditions: Vec<Condition>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WeatherOutput {
-    #[serde(alias = "fxLink")]
+    #[serde(default)]
+    pub(crate) code: String,
+    #[serde(alias = "fxLink", default)]
     fx_link: String,
+    #[serde(default)]
     daily: Vec<Weather>,
+    #[serde(default)]
+    hourly: Vec<HourlyWeather>,
+    // The conditions which fired, if any are configured.
+    #[serde(skip)]
+    alerts: String,
 }
-#[derive(Debug, Deserialize)]
-struct Weather {
+
+impl WeatherOutput {
+    pub(crate) fn new(daily: Vec<Weather>, hourly: Vec<HourlyWeather>) -> Self {
+        WeatherOutput {
+            code: String::new(),
+            fx_link: String::new(),
+            daily,
+            hourly,
+            alerts: String::new(),
+        }
+    }
+
+    // The date of the first forecast, like `2020-07-01`.
+    fn date(&self) -> &str {
+        match self.daily.first() {
+            Some(day) => &day.fx_date,
+            None => self
+                .hourly
+                .first()
+                .and_then(|hour| hour.fx_time.get(..10))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Weather {
+    #[serde(alias = "fxDate")]
+    pub(crate) fx_date: String,
+    pub(crate) sunrise: String,
+    pub(crate) sunset: String,
     #[serde(alias = "tempMax")]
-    temp_max: String,
+    pub(crate) temp_max: String,
     #[serde(alias = "tempMin")]
-    temp_min: String,
+    pub(crate) temp_min: String,
     #[serde(alias = "textDay")]
-    text_day: String,
+    pub(crate) text_day: String,
+    #[serde(alias = "textNight")]
+    pub(crate) text_night: String,
     #[serde(alias = "windDirDay")]
-    wind_dir_day: String,
+    pub(crate) wind_dir_day: String,
     #[serde(alias = "windSpeedDay")]
-    wind_speed_day: String,
+    pub(crate) wind_speed_day: String,
     #[serde(alias = "windScaleDay")]
-    wind_scale_day: String,
+    pub(crate) wind_scale_day: String,
+    #[serde(alias = "windDirNight")]
+    pub(crate) wind_dir_night: String,
+    #[serde(alias = "windSpeedNight")]
+    pub(crate) wind_speed_night: String,
+    #[serde(alias = "windScaleNight")]
+    pub(crate) wind_scale_night: String,
+    pub(crate) humidity: String,
+    pub(crate) precip: String,
+    pub(crate) pressure: String,
+    #[serde(alias = "uvIndex")]
+    pub(crate) uv_index: String,
+    pub(crate) vis: String,
+    pub(crate) cloud: String,
+}
+
+impl Weather {
+    fn field(&self, field: &str) -> Option<&str> {
+        let value = match field {
+            "fx_date" => &self.fx_date,
+            "sunrise" => &self.sunrise,
+            "sunset" => &self.sunset,
+            "temp_max" => &self.temp_max,
+            "temp_min" => &self.temp_min,
+            "text_day" => &self.text_day,
+            "text_night" => &self.text_night,
+            "wind_dir_day" => &self.wind_dir_day,
+            "wind_speed_day" => &self.wind_speed_day,
+            "wind_scale_day" => &self.wind_scale_day,
+            "wind_dir_night" => &self.wind_dir_night,
+            "wind_speed_night" => &self.wind_speed_night,
+            "wind_scale_night" => &self.wind_scale_night,
+            "humidity" => &self.humidity,
+            "precip" => &self.precip,
+            "pressure" => &self.pressure,
+            "uv_index" => &self.uv_index,
+            "vis" => &self.vis,
+            "cloud" => &self.cloud,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct HourlyWeather {
+    #[serde(alias = "fxTime")]
+    pub(crate) fx_time: String,
+    pub(crate) temp: String,
+    pub(crate) text: String,
+    #[serde(alias = "windDir")]
+    pub(crate) wind_dir: String,
+    #[serde(alias = "windSpeed")]
+    pub(crate) wind_speed: String,
+    #[serde(alias = "windScale")]
+    pub(crate) wind_scale: String,
+    pub(crate) humidity: String,
+    pub(crate) pop: String,
+    pub(crate) precip: String,
+    pub(crate) pressure: String,
+    pub(crate) cloud: String,
 }
 
+impl HourlyWeather {
+    fn field(&self, field: &str) -> Option<&str> {
+        let value = match field {
+            "fx_time" => &self.fx_time,
+            "temp" => &self.temp,
+            "text" => &self.text,
+            "wind_dir" => &self.wind_dir,
+            "wind_speed" => &self.wind_speed,
+            "wind_scale" => &self.wind_scale,
+            "humidity" => &self.humidity,
+            "pop" => &self.pop,
+            "precip" => &self.precip,
+            "pressure" => &self.pressure,
+            "cloud" => &self.cloud,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+// Fields without a prefix refer to today (or the current hour in hourly mode),
+// other forecasts are addressed like `tomorrow.text_day`, `daily.2.temp_max`
+// or `hourly.3.temp`.
 impl<'a> std::ops::Index<&'a str> for WeatherOutput {
     type Output = str;
     fn index(&self, field: &'a str) -> &Self::Output {
-        let weather = self.daily.first().expect("No weather found.");
-        match field {
-            "fx_link" => &self.fx_link,
-            "temp_max" => &weather.temp_max,
-            "temp_min" => &weather.temp_min,
-            "text_day" => &weather.text_day,
-            "wind_dir_day" => &weather.wind_dir_day,
-            "wind_speed_day" => &weather.wind_speed_day,
-            "wind_scale_day" => &weather.wind_scale_day,
-            _ => "",
-        }
+        let indexed = |prefix: &str| {
+            let rest = field.strip_prefix(prefix)?;
+            let (idx, name) = rest.split_once('.')?;
+            Some((idx.parse::<usize>().ok()?, name))
+        };
+
+        let value = if field == "fx_link" {
+            Some(self.fx_link.as_str())
+        } else if field == "alerts" {
+            Some(self.alerts.as_str())
+        } else if let Some(name) = field.strip_prefix("today.") {
+            self.daily.first().and_then(|day| day.field(name))
+        } else if let Some(name) = field.strip_prefix("tomorrow.") {
+            self.daily.get(1).and_then(|day| day.field(name))
+        } else if let Some((idx, name)) = indexed("daily.") {
+            self.daily.get(idx).and_then(|day| day.field(name))
+        } else if let Some((idx, name)) = indexed("hourly.") {
+            self.hourly.get(idx).and_then(|hour| hour.field(name))
+        } else if let Some(day) = self.daily.first() {
+            day.field(field)
+        } else {
+            self.hourly.first().and_then(|hour| hour.field(field))
+        };
+
+        value.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test_weather {
+    use super::*;
+
+    #[test]
+    fn test_index() {
+        let output: WeatherOutput = serde_json::from_str(
+            r#"{
+                "code": "200",
+                "fxLink": "http://hfx.link/2ax1",
+                "daily": [
+                    {"fxDate": "2020-07-01", "tempMax": "30", "textDay": "Sunny", "uvIndex": "9"},
+                    {"fxDate": "2020-07-02", "tempMax": "25", "textNight": "Rain"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(&output["temp_max"], "30");
+        assert_eq!(&output["today.uv_index"], "9");
+        assert_eq!(&output["tomorrow.text_night"], "Rain");
+        assert_eq!(&output["daily.1.fx_date"], "2020-07-02");
+        assert_eq!(&output["daily.5.temp_max"], "");
+        assert_eq!(&output["hourly.0.temp"], "");
+        assert_eq!(&output["fx_link"], "http://hfx.link/2ax1");
     }
 }
@@ -0,0 +1,547 @@
+use crate::weather::{HourlyWeather, Weather, WeatherConfig, WeatherError, WeatherOutput};
+use anyhow::Result;
+use chrono::{FixedOffset, TimeZone};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+// Where forecasts come from. Every provider is normalized into the field
+// names HeWeather uses, so templates work whichever one is configured.
+//
+// HeWeather takes a location id or `longitude,latitude`, the others take
+// `latitude,longitude`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeatherProvider {
+    HeWeather,
+    OpenMeteo,
+    OpenWeatherMap,
+}
+
+#[derive(Debug)]
+pub struct UnknownProvider(String);
+
+impl Display for UnknownProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown weather provider {}.", self.0)
+    }
+}
+
+impl std::error::Error for UnknownProvider {}
+
+impl FromStr for WeatherProvider {
+    type Err = UnknownProvider;
+    fn from_str(provider: &str) -> Result<Self, Self::Err> {
+        match provider {
+            "heweather" => Ok(WeatherProvider::HeWeather),
+            "open_meteo" => Ok(WeatherProvider::OpenMeteo),
+            "openweathermap" => Ok(WeatherProvider::OpenWeatherMap),
+            _ => Err(UnknownProvider(provider.to_string())),
+        }
+    }
+}
+
+impl WeatherProvider {
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            WeatherProvider::HeWeather => "https://devapi.heweather.net",
+            WeatherProvider::OpenMeteo => "https://api.open-meteo.com",
+            WeatherProvider::OpenWeatherMap => "https://api.openweathermap.org",
+        }
+    }
+
+    // Open-Meteo is free to use without an API key.
+    pub fn needs_key(self) -> bool {
+        self != WeatherProvider::OpenMeteo
+    }
+
+    pub async fn fetch(self, client: &Client, config: &WeatherConfig) -> Result<WeatherOutput> {
+        match self {
+            WeatherProvider::HeWeather => fetch_heweather(client, config).await,
+            WeatherProvider::OpenMeteo => fetch_open_meteo(client, config).await,
+            WeatherProvider::OpenWeatherMap => fetch_openweathermap(client, config).await,
+        }
+    }
+}
+
+// `3d` asks for 3 days and `24h` for 24 hours.
+fn parse_mode(mode: &str) -> (bool, usize) {
+    let hourly = mode.ends_with('h');
+    let count = mode
+        .trim_end_matches(&['d', 'h'][..])
+        .parse()
+        .unwrap_or(if hourly { 24 } else { 3 });
+
+    (hourly, count)
+}
+
+fn lat_lon(location: &str) -> (&str, &str) {
+    let (lat, lon) = location.split_once(',').unwrap_or((location, ""));
+    (lat.trim(), lon.trim())
+}
+
+async fn fetch_heweather(client: &Client, config: &WeatherConfig) -> Result<WeatherOutput> {
+    let res: WeatherOutput = client
+        .get(&format!("{}/v7/weather/{}", config.base_url, config.mode))
+        .query(&[("key", &config.key), ("location", &config.location)])
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if res.code != "200" {
+        return Err(WeatherError::Api(res.code).into());
+    }
+
+    Ok(res)
+}
+
+// Open-Meteo answers with one array per variable, e.g. `daily.temperature_2m_max`.
+#[derive(Deserialize)]
+struct OpenMeteoResponse {
+    #[serde(default)]
+    daily: HashMap<String, Vec<Value>>,
+    #[serde(default)]
+    hourly: HashMap<String, Vec<Value>>,
+}
+
+const OPEN_METEO_DAILY: &str = "weather_code,temperature_2m_max,temperature_2m_min,\
+     wind_speed_10m_max,wind_direction_10m_dominant,precipitation_sum,\
+     precipitation_probability_max,uv_index_max,sunrise,sunset";
+const OPEN_METEO_HOURLY: &str = "weather_code,temperature_2m,wind_speed_10m,\
+     wind_direction_10m,relative_humidity_2m,precipitation_probability,precipitation,\
+     pressure_msl,cloud_cover";
+
+async fn fetch_open_meteo(client: &Client, config: &WeatherConfig) -> Result<WeatherOutput> {
+    let (hourly, count) = parse_mode(&config.mode);
+    let (lat, lon) = lat_lon(&config.location);
+    let count = count.to_string();
+    let mut query = vec![("latitude", lat), ("longitude", lon), ("timezone", "auto")];
+    if hourly {
+        query.extend(&[("hourly", OPEN_METEO_HOURLY), ("forecast_hours", &count)]);
+    } else {
+        query.extend(&[("daily", OPEN_METEO_DAILY), ("forecast_days", &count)]);
+    }
+
+    let res: OpenMeteoResponse = client
+        .get(&format!("{}/v1/forecast", config.base_url))
+        .query(&query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let column = |columns: &HashMap<String, Vec<Value>>, name: &str, idx: usize| {
+        columns
+            .get(name)
+            .and_then(|values| values.get(idx))
+            .map(value_to_string)
+            .unwrap_or_default()
+    };
+    let time_of_day = |time: String| time.get(11..16).unwrap_or_default().to_string();
+
+    let days = res.daily.get("time").map_or(0, Vec::len);
+    let daily = (0..days)
+        .map(|idx| {
+            let wind_speed = column(&res.daily, "wind_speed_10m_max", idx);
+            let wind_dir = column(&res.daily, "wind_direction_10m_dominant", idx);
+            Weather {
+                fx_date: column(&res.daily, "time", idx),
+                sunrise: time_of_day(column(&res.daily, "sunrise", idx)),
+                sunset: time_of_day(column(&res.daily, "sunset", idx)),
+                temp_max: column(&res.daily, "temperature_2m_max", idx),
+                temp_min: column(&res.daily, "temperature_2m_min", idx),
+                text_day: wmo_text(&column(&res.daily, "weather_code", idx)).to_string(),
+                wind_dir_day: compass(&wind_dir).to_string(),
+                wind_scale_day: beaufort(&wind_speed),
+                wind_speed_day: wind_speed,
+                precip: column(&res.daily, "precipitation_sum", idx),
+                uv_index: column(&res.daily, "uv_index_max", idx),
+                ..Weather::default()
+            }
+        })
+        .collect();
+
+    let hours = res.hourly.get("time").map_or(0, Vec::len);
+    let hourly = (0..hours)
+        .map(|idx| {
+            let wind_speed = column(&res.hourly, "wind_speed_10m", idx);
+            HourlyWeather {
+                fx_time: column(&res.hourly, "time", idx),
+                temp: column(&res.hourly, "temperature_2m", idx),
+                text: wmo_text(&column(&res.hourly, "weather_code", idx)).to_string(),
+                wind_dir: compass(&column(&res.hourly, "wind_direction_10m", idx)).to_string(),
+                wind_scale: beaufort(&wind_speed),
+                wind_speed,
+                humidity: column(&res.hourly, "relative_humidity_2m", idx),
+                pop: column(&res.hourly, "precipitation_probability", idx),
+                precip: column(&res.hourly, "precipitation", idx),
+                pressure: column(&res.hourly, "pressure_msl", idx),
+                cloud: column(&res.hourly, "cloud_cover", idx),
+            }
+        })
+        .collect();
+
+    Ok(WeatherOutput::new(daily, hourly))
+}
+
+#[derive(Deserialize)]
+struct OwmResponse {
+    #[serde(default)]
+    timezone_offset: i32,
+    #[serde(default)]
+    daily: Vec<OwmDaily>,
+    #[serde(default)]
+    hourly: Vec<OwmHourly>,
+}
+
+#[derive(Deserialize)]
+struct OwmDaily {
+    dt: i64,
+    sunrise: i64,
+    sunset: i64,
+    temp: OwmTemp,
+    pressure: f64,
+    humidity: f64,
+    wind_speed: f64,
+    wind_deg: f64,
+    clouds: f64,
+    #[serde(default)]
+    rain: f64,
+    #[serde(default)]
+    uvi: f64,
+    weather: Vec<OwmCondition>,
+}
+
+#[derive(Deserialize)]
+struct OwmTemp {
+    min: f64,
+    max: f64,
+}
+
+#[derive(Deserialize)]
+struct OwmHourly {
+    dt: i64,
+    temp: f64,
+    pressure: f64,
+    humidity: f64,
+    clouds: f64,
+    wind_speed: f64,
+    wind_deg: f64,
+    #[serde(default)]
+    pop: f64,
+    #[serde(default)]
+    rain: Option<OwmRain>,
+    weather: Vec<OwmCondition>,
+}
+
+#[derive(Deserialize)]
+struct OwmRain {
+    #[serde(rename = "1h", default)]
+    one_hour: f64,
+}
+
+#[derive(Deserialize)]
+struct OwmCondition {
+    description: String,
+}
+
+async fn fetch_openweathermap(client: &Client, config: &WeatherConfig) -> Result<WeatherOutput> {
+    let (hourly, count) = parse_mode(&config.mode);
+    let (lat, lon) = lat_lon(&config.location);
+    let exclude = if hourly {
+        "current,minutely,daily,alerts"
+    } else {
+        "current,minutely,hourly,alerts"
+    };
+
+    let res: OwmResponse = client
+        .get(&format!("{}/data/3.0/onecall", config.base_url))
+        .query(&[
+            ("lat", lat),
+            ("lon", lon),
+            ("appid", &config.key),
+            ("units", "metric"),
+            ("exclude", exclude),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let offset = FixedOffset::east_opt(res.timezone_offset)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).expect("UTC is a valid offset."));
+    let format = |timestamp: i64, format: &str| {
+        offset
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|time| time.format(format).to_string())
+            .unwrap_or_default()
+    };
+    let description = |weather: &[OwmCondition]| {
+        weather
+            .first()
+            .map(|condition| condition.description.clone())
+            .unwrap_or_default()
+    };
+    // OpenWeatherMap reports m/s, HeWeather km/h.
+    let km_per_hour = |speed: f64| format!("{:.0}", speed * 3.6);
+
+    let daily = res
+        .daily
+        .iter()
+        .take(count)
+        .map(|day| {
+            let wind_speed = km_per_hour(day.wind_speed);
+            Weather {
+                fx_date: format(day.dt, "%Y-%m-%d"),
+                sunrise: format(day.sunrise, "%H:%M"),
+                sunset: format(day.sunset, "%H:%M"),
+                temp_max: format!("{:.0}", day.temp.max),
+                temp_min: format!("{:.0}", day.temp.min),
+                text_day: description(&day.weather),
+                wind_dir_day: compass(&day.wind_deg.to_string()).to_string(),
+                wind_scale_day: beaufort(&wind_speed),
+                wind_speed_day: wind_speed,
+                humidity: day.humidity.to_string(),
+                precip: day.rain.to_string(),
+                pressure: day.pressure.to_string(),
+                uv_index: format!("{:.0}", day.uvi),
+                cloud: day.clouds.to_string(),
+                ..Weather::default()
+            }
+        })
+        .collect();
+
+    let hourly = res
+        .hourly
+        .iter()
+        .take(count)
+        .map(|hour| {
+            let wind_speed = km_per_hour(hour.wind_speed);
+            HourlyWeather {
+                fx_time: format(hour.dt, "%Y-%m-%dT%H:%M%:z"),
+                temp: format!("{:.0}", hour.temp),
+                text: description(&hour.weather),
+                wind_dir: compass(&hour.wind_deg.to_string()).to_string(),
+                wind_scale: beaufort(&wind_speed),
+                wind_speed,
+                humidity: hour.humidity.to_string(),
+                pop: format!("{:.0}", hour.pop * 100.0),
+                precip: hour
+                    .rain
+                    .as_ref()
+                    .map_or(0.0, |rain| rain.one_hour)
+                    .to_string(),
+                pressure: hour.pressure.to_string(),
+                cloud: hour.clouds.to_string(),
+            }
+        })
+        .collect();
+
+    Ok(WeatherOutput::new(daily, hourly))
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn compass(degrees: &str) -> &'static str {
+    const DIRECTIONS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    match degrees.parse::<f64>() {
+        Ok(degrees) => DIRECTIONS[((degrees.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8],
+        Err(_) => "",
+    }
+}
+
+// The Beaufort scale of a wind speed in km/h.
+fn beaufort(speed: &str) -> String {
+    const UPPER_BOUNDS: [f64; 12] = [
+        1.0, 6.0, 12.0, 20.0, 29.0, 39.0, 50.0, 62.0, 75.0, 89.0, 103.0, 118.0,
+    ];
+    match speed.parse::<f64>() {
+        Ok(speed) => UPPER_BOUNDS
+            .iter()
+            .position(|bound| speed < *bound)
+            .unwrap_or(UPPER_BOUNDS.len())
+            .to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+// https://open-meteo.com/en/docs#weathervariables
+fn wmo_text(code: &str) -> &'static str {
+    match code {
+        "0" => "Clear",
+        "1" => "Mainly Clear",
+        "2" => "Partly Cloudy",
+        "3" => "Overcast",
+        "45" | "48" => "Fog",
+        "51" | "53" | "55" => "Drizzle",
+        "56" | "57" => "Freezing Drizzle",
+        "61" => "Light Rain",
+        "63" => "Rain",
+        "65" => "Heavy Rain",
+        "66" | "67" => "Freezing Rain",
+        "71" => "Light Snow",
+        "73" => "Snow",
+        "75" => "Heavy Snow",
+        "77" => "Snow Grains",
+        "80" | "81" | "82" => "Rain Showers",
+        "85" | "86" => "Snow Showers",
+        "95" => "Thunderstorm",
+        "96" | "99" => "Thunderstorm With Hail",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod test_weather_provider {
+    use super::*;
+    use crate::test_server::{TestResponse, TestServer};
+    use async_std::task;
+
+    #[test]
+    fn test_open_meteo() {
+        let server = TestServer::serve(vec![TestResponse::new(
+            200,
+            r#"{
+                "daily": {
+                    "time": ["2020-07-01", "2020-07-02"],
+                    "weather_code": [61, 0],
+                    "temperature_2m_max": [36.2, 30.1],
+                    "temperature_2m_min": [25.0, 22.4],
+                    "wind_speed_10m_max": [40.0, 3.0],
+                    "wind_direction_10m_dominant": [180, 350],
+                    "uv_index_max": [7.5, null],
+                    "sunrise": ["2020-07-01T04:50", "2020-07-02T04:51"]
+                }
+            }"#,
+        )]);
+        let config = WeatherConfig {
+            key: String::new(),
+            location: "31.23,121.47".to_string(),
+            mode: "2d".to_string(),
+            provider: WeatherProvider::OpenMeteo,
+            base_url: server.url.clone(),
+            conditions: Vec::new(),
+        };
+
+        let output =
+            task::block_on(WeatherProvider::OpenMeteo.fetch(&Client::new(), &config)).unwrap();
+        assert_eq!(&output["temp_max"], "36.2");
+        assert_eq!(&output["text_day"], "Light Rain");
+        assert_eq!(&output["wind_dir_day"], "S");
+        assert_eq!(&output["wind_scale_day"], "6");
+        assert_eq!(&output["sunrise"], "04:50");
+        assert_eq!(&output["tomorrow.wind_dir_day"], "N");
+        assert_eq!(&output["tomorrow.uv_index"], "");
+
+        let request = server.request();
+        assert!(request
+            .path
+            .starts_with("/v1/forecast?latitude=31.23&longitude=121.47"));
+        assert!(request.path.contains("forecast_days=2"));
+    }
+
+    #[test]
+    fn test_heweather() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(
+                200,
+                r#"{
+                    "code": "200",
+                    "hourly": [
+                        {"fxTime": "2020-07-01T08:00+08:00", "temp": "28", "text": "Cloudy", "pop": "20"},
+                        {"fxTime": "2020-07-01T09:00+08:00", "temp": "30", "text": "Rain", "pop": "80"}
+                    ]
+                }"#,
+            ),
+            TestResponse::new(200, r#"{"code": "401"}"#),
+        ]);
+        let config = WeatherConfig {
+            key: "key".to_string(),
+            location: "101010100".to_string(),
+            mode: "24h".to_string(),
+            provider: WeatherProvider::HeWeather,
+            base_url: server.url.clone(),
+            conditions: Vec::new(),
+        };
+
+        let output =
+            task::block_on(WeatherProvider::HeWeather.fetch(&Client::new(), &config)).unwrap();
+        assert_eq!(&output["temp"], "28");
+        assert_eq!(&output["hourly.1.text"], "Rain");
+        assert_eq!(&output["hourly.1.pop"], "80");
+        assert_eq!(
+            server.request().path,
+            "/v7/weather/24h?key=key&location=101010100"
+        );
+
+        // The API answers errors with a 200 status and its own code.
+        let err =
+            task::block_on(WeatherProvider::HeWeather.fetch(&Client::new(), &config)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(WeatherError::Api(code)) if code == "401"
+        ));
+    }
+
+    #[test]
+    fn test_openweathermap() {
+        let hour = |dt: i64, rain: &str| {
+            format!(
+                r#"{{"dt": {}, "temp": 26.6, "pressure": 1008, "humidity": 80, "clouds": 75,
+                    "wind_speed": 5.0, "wind_deg": 90, "pop": 0.35, {}
+                    "weather": [{{"description": "light rain"}}]}}"#,
+                dt, rain
+            )
+        };
+        let body = format!(
+            r#"{{"timezone_offset": 28800, "hourly": [{}, {}, {}]}}"#,
+            hour(1593561600, r#""rain": {"1h": 0.6},"#),
+            hour(1593565200, ""),
+            hour(1593568800, "")
+        );
+        let server = TestServer::serve(vec![TestResponse::new(200, body)]);
+        let config = WeatherConfig {
+            key: "key".to_string(),
+            location: "31.23,121.47".to_string(),
+            mode: "2h".to_string(),
+            provider: WeatherProvider::OpenWeatherMap,
+            base_url: server.url.clone(),
+            conditions: Vec::new(),
+        };
+
+        let output =
+            task::block_on(WeatherProvider::OpenWeatherMap.fetch(&Client::new(), &config)).unwrap();
+        // Times are local to the location.
+        assert_eq!(&output["fx_time"], "2020-07-01T08:00+08:00");
+        assert_eq!(&output["temp"], "27");
+        // 5 m/s is 18 km/h.
+        assert_eq!(&output["wind_speed"], "18");
+        assert_eq!(&output["wind_scale"], "3");
+        assert_eq!(&output["wind_dir"], "E");
+        assert_eq!(&output["pop"], "35");
+        assert_eq!(&output["precip"], "0.6");
+        assert_eq!(&output["hourly.1.fx_time"], "2020-07-01T09:00+08:00");
+        assert_eq!(&output["hourly.1.precip"], "0");
+        assert_eq!(&output["hourly.2.temp"], "");
+
+        let request = server.request();
+        assert!(request
+            .path
+            .starts_with("/data/3.0/onecall?lat=31.23&lon=121.47&appid=key&units=metric"));
+        assert!(request
+            .path
+            .contains("exclude=current%2Cminutely%2Cdaily%2Calerts"));
+    }
+}
@@ -1,39 +1,388 @@
-use crate::Sink;
+use crate::config::ConfigError;
+use crate::template::Template;
+use crate::{Indexable, Sink};
 use anyhow::Result;
+use async_std::task;
 use async_trait::async_trait;
-use reqwest::Method;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
+use std::time::Duration;
+
+// How much of a response body is kept in errors.
+const ERROR_BODY_LIMIT: usize = 512;
+const MAX_ATTEMPTS: usize = 3;
+const MAX_RETRY_DELAY: f64 = 60.0;
+
+// A service answered with an error code in an otherwise successful response.
+#[derive(Debug)]
+pub struct ApiError {
+    pub service: &'static str,
+    pub code: String,
+    pub message: String,
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error {}: {}", self.service, self.code, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Fails unless the JSON field `code` of the body equals `success`.
+pub(crate) fn check_api_code(
+    service: &'static str,
+    body: &str,
+    code: &str,
+    success: &str,
+    message: &str,
+) -> Result<()> {
+    let actual = json_field(body, code).unwrap_or_default();
+    if actual == success {
+        return Ok(());
+    }
+
+    Err(ApiError {
+        service,
+        code: actual,
+        message: json_field(body, message).unwrap_or_else(|| truncate(body.to_string())),
+    }
+    .into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageFormat {
+    Text,
+    Markdown,
+}
+
+impl FromStr for MessageFormat {
+    type Err = ConfigError;
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(MessageFormat::Text),
+            "markdown" => Ok(MessageFormat::Markdown),
+            _ => Err(ConfigError::UnsupportedValue(format.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WebSinkError {
+    UnexpectedStatus { status: StatusCode, body: String },
+    UnexpectedBody { status: StatusCode, body: String },
+}
+
+impl Display for WebSinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WebSinkError::UnexpectedStatus { status, body } => {
+                write!(f, "Unexpected status {}: {}", status, body)
+            }
+            WebSinkError::UnexpectedBody { status, body } => {
+                write!(f, "Unexpected response body ({}): {}", status, body)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WebSinkError {}
 
 pub struct WebSink {
     config: WebConfig,
 }
 
 impl WebSink {
-    pub fn new(method: impl Into<String>, url: impl Into<String>) -> Self {
-        WebSink {
-            config: WebConfig {
-                method: method.into(),
-                url: url.into(),
-            },
-        }
+    pub fn new(config: WebConfig) -> Self {
+        WebSink { config }
     }
 }
 
 #[async_trait(?Send)]
 impl Sink for WebSink {
-    async fn sink(&self, input: String) -> Result<()> {
+    async fn sink(&self, input: String, item: &dyn Indexable) -> Result<()> {
+        let config = &self.config;
         let client = reqwest::Client::new();
-        client
-            .request(Method::from_str(&self.config.method)?, &self.config.url)
-            .body(input)
+        let url = config.url.render(item);
+        let query: Vec<_> = config
+            .query
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.render(item)))
+            .collect();
+
+        let mut request = client
+            .request(Method::from_str(&config.method)?, &url)
+            .query(&query);
+        for (name, value) in config.headers.iter() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(ref content_type) = config.content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, content_type.as_str());
+        }
+        if let Some(ref token) = config.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some((ref username, ref password)) = config.basic_auth {
+            request = request.basic_auth(username, password.as_ref());
+        }
+
+        let res = request.body(input).send().await?;
+        self.config.check_response(res).await
+    }
+}
+
+pub struct WebConfig {
+    pub method: String,
+    // Literal text unless the `template_url` config is set.
+    pub url: Template,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, Template)>,
+    pub content_type: Option<String>,
+    pub bearer_token: Option<String>,
+    pub basic_auth: Option<(String, Option<String>)>,
+    // Any 2xx status is accepted when this is empty.
+    pub expected_status: Vec<u16>,
+    pub assert_body_contains: Option<String>,
+    // `path=value`, where path is dotted like `data.code`.
+    pub assert_json: Option<(String, String)>,
+}
+
+impl WebConfig {
+    async fn check_response(&self, res: Response) -> Result<()> {
+        let status = res.status();
+        let body = res.text().await?;
+
+        let status_ok = if self.expected_status.is_empty() {
+            status.is_success()
+        } else {
+            self.expected_status.contains(&status.as_u16())
+        };
+        if !status_ok {
+            let body = truncate(body);
+            return Err(WebSinkError::UnexpectedStatus { status, body }.into());
+        }
+
+        let contains_ok = match self.assert_body_contains {
+            Some(ref expected) => body.contains(expected.as_str()),
+            None => true,
+        };
+        let json_ok = match self.assert_json {
+            Some((ref path, ref expected)) => json_field(&body, path).as_ref() == Some(expected),
+            None => true,
+        };
+        if !contains_ok || !json_ok {
+            let body = truncate(body);
+            return Err(WebSinkError::UnexpectedBody { status, body }.into());
+        }
+
+        Ok(())
+    }
+}
+
+fn json_field(body: &str, path: &str) -> Option<String> {
+    let json: Value = serde_json::from_str(body).ok()?;
+    let pointer = format!("/{}", path.replace('.', "/"));
+    Some(field_text(json.pointer(&pointer)?))
+}
+
+// Lists, such as the `errors` of Pushover, are joined.
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(field_text).collect::<Vec<_>>().join("; "),
+        other => other.to_string(),
+    }
+}
+
+// Fails unless the status is 2xx, returning the body otherwise.
+pub(crate) async fn ensure_success(res: Response) -> Result<String> {
+    let status = res.status();
+    let body = res.text().await?;
+    if !status.is_success() {
+        let body = truncate(body);
+        return Err(WebSinkError::UnexpectedStatus { status, body }.into());
+    }
+
+    Ok(body)
+}
+
+// Sends the request again when it's rate limited, after waiting for as long as
+// the `Retry-After` header or a `retry_after` field in the body asks.
+pub(crate) async fn send_with_retry(request: RequestBuilder) -> Result<Response> {
+    let mut attempt = 1;
+    loop {
+        let res = request
+            .try_clone()
+            .expect("Streaming bodies can't be retried.")
             .send()
             .await?;
+        if res.status() != StatusCode::TOO_MANY_REQUESTS || attempt >= MAX_ATTEMPTS {
+            return Ok(res);
+        }
 
-        Ok(())
+        let header = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<f64>().ok());
+        let delay = match header {
+            Some(delay) => delay,
+            None => {
+                let body = res.text().await?;
+                json_field(&body, "retry_after")
+                    .or_else(|| json_field(&body, "parameters.retry_after"))
+                    .and_then(|delay| delay.parse().ok())
+                    .unwrap_or(1.0)
+            }
+        };
+
+        let delay = if delay.is_finite() {
+            delay.clamp(0.0, MAX_RETRY_DELAY)
+        } else {
+            1.0
+        };
+        task::sleep(Duration::from_secs_f64(delay)).await;
+        attempt += 1;
     }
 }
 
-pub struct WebConfig {
-    method: String,
-    url: String,
+// Splits text into chunks of at most `limit` chars, cutting at the last line
+// break where possible.
+pub(crate) fn split_text(text: &str, limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+    let mut last_newline = None;
+
+    for (idx, c) in text.char_indices() {
+        if size >= limit && idx > start {
+            match last_newline {
+                Some(newline) if newline > start => {
+                    chunks.push(text[start..newline].to_string());
+                    start = newline + 1;
+                }
+                _ => {
+                    chunks.push(text[start..idx].to_string());
+                    start = idx;
+                }
+            }
+            size = text[start..idx].chars().count();
+            last_newline = None;
+        }
+        if c == '\n' {
+            last_newline = Some(idx);
+        }
+        size += 1;
+    }
+    if start < text.len() || chunks.is_empty() {
+        chunks.push(text[start..].to_string());
+    }
+
+    chunks
+}
+
+fn truncate(mut body: String) -> String {
+    if let Some((idx, _)) = body.char_indices().nth(ERROR_BODY_LIMIT) {
+        body.truncate(idx);
+        body.push_str("...");
+    }
+    body
+}
+
+// Parses `path=value` for `assert_json`.
+pub fn parse_json_assertion(value: &str) -> (String, String) {
+    match value.split_once('=') {
+        Some((path, expected)) => (path.trim().to_string(), expected.trim().to_string()),
+        None => (value.trim().to_string(), String::new()),
+    }
+}
+
+// Parses `user:password`, the password is optional.
+pub fn parse_basic_auth(value: &str) -> (String, Option<String>) {
+    match value.split_once(':') {
+        Some((username, password)) => (username.to_string(), Some(password.to_string())),
+        None => (value.to_string(), None),
+    }
+}
+
+#[cfg(test)]
+mod test_web {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use async_std::task;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![TestResponse::new(200, "")]);
+        let sink = WebSink::new(WebConfig {
+            method: "POST".to_string(),
+            url: format!("{}/hooks/{{id}}", server.url).parse().unwrap(),
+            headers: vec![("X-Api-Key".to_string(), "secret".to_string())],
+            query: vec![("title".to_string(), "{title}".parse().unwrap())],
+            content_type: Some("application/json".to_string()),
+            bearer_token: Some("token".to_string()),
+            basic_auth: None,
+            expected_status: Vec::new(),
+            assert_body_contains: None,
+            assert_json: None,
+        });
+        let item = TestItem::new(&[("id", "42"), ("title", "a b&c")]);
+
+        task::block_on(sink.sink(r#"{"a":1}"#.to_string(), &item)).unwrap();
+
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hooks/42?title=a+b%26c");
+        assert_eq!(request.headers["x-api-key"], "secret");
+        assert_eq!(request.headers["authorization"], "Bearer token");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(request.body, r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn test_check_response() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(401, "unauthorized"),
+            TestResponse::new(200, r#"{"errcode": 40001}"#),
+            TestResponse::new(202, r#"{"errcode": 0}"#),
+        ]);
+        let sink = WebSink::new(WebConfig {
+            method: "POST".to_string(),
+            url: Template::literal(server.url.clone()),
+            headers: Vec::new(),
+            query: Vec::new(),
+            content_type: None,
+            bearer_token: None,
+            basic_auth: None,
+            expected_status: vec![200, 202],
+            assert_body_contains: None,
+            assert_json: Some(parse_json_assertion("errcode=0")),
+        });
+        let item = TestItem::new(&[]);
+        let sink_error = || {
+            let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+            err.downcast::<WebSinkError>().unwrap()
+        };
+
+        match sink_error() {
+            WebSinkError::UnexpectedStatus { status, body } => {
+                assert_eq!(status, StatusCode::UNAUTHORIZED);
+                assert_eq!(body, "unauthorized");
+            }
+            err => panic!("Unexpected error {:?}", err),
+        }
+        assert!(matches!(sink_error(), WebSinkError::UnexpectedBody { .. }));
+        task::block_on(sink.sink(String::new(), &item)).unwrap();
+    }
+
+    #[test]
+    fn test_split_text() {
+        assert_eq!(split_text("", 3), vec![""]);
+        assert_eq!(split_text("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(split_text("ab\ncdef", 4), vec!["ab", "cdef"]);
+        assert_eq!(split_text("éèêë", 2), vec!["éè", "êë"]);
+    }
 }
@@ -0,0 +1,84 @@
+use crate::web::{check_api_code, ensure_success, MessageFormat};
+use crate::{Indexable, Sink};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+// Posts to a WeCom (WeChat Work) group robot.
+pub struct WeComSink {
+    config: WeComConfig,
+}
+
+pub struct WeComConfig {
+    // The full webhook url including the key.
+    pub webhook_url: String,
+    pub format: MessageFormat,
+}
+
+impl WeComSink {
+    pub fn new(config: WeComConfig) -> Self {
+        WeComSink { config }
+    }
+}
+
+#[async_trait(?Send)]
+impl Sink for WeComSink {
+    async fn sink(&self, input: String, _item: &dyn Indexable) -> Result<()> {
+        let payload = match self.config.format {
+            MessageFormat::Text => json!({"msgtype": "text", "text": { "content": input }}),
+            MessageFormat::Markdown => {
+                json!({"msgtype": "markdown", "markdown": { "content": input }})
+            }
+        };
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&self.config.webhook_url)
+            .json(&payload)
+            .send()
+            .await?;
+
+        let body = ensure_success(res).await?;
+        check_api_code("WeCom", &body, "errcode", "0", "errmsg")
+    }
+}
+
+#[cfg(test)]
+mod test_wecom {
+    use super::*;
+    use crate::test_server::{TestItem, TestResponse, TestServer};
+    use crate::web::ApiError;
+    use async_std::task;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sink() {
+        let server = TestServer::serve(vec![
+            TestResponse::new(200, r#"{"errcode": 0, "errmsg": "ok"}"#),
+            TestResponse::new(
+                200,
+                r#"{"errcode": 93000, "errmsg": "invalid webhook url"}"#,
+            ),
+        ]);
+        let sink = WeComSink::new(WeComConfig {
+            webhook_url: format!("{}/cgi-bin/webhook/send?key=abc", server.url),
+            format: MessageFormat::Text,
+        });
+        let item = TestItem::new(&[]);
+
+        task::block_on(sink.sink("hi".to_string(), &item)).unwrap();
+        let request = server.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/cgi-bin/webhook/send?key=abc");
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            payload,
+            json!({"msgtype": "text", "text": {"content": "hi"}})
+        );
+
+        let err = task::block_on(sink.sink(String::new(), &item)).unwrap_err();
+        let err = err.downcast::<ApiError>().unwrap();
+        assert_eq!(err.code, "93000");
+        assert_eq!(err.message, "invalid webhook url");
+    }
+}